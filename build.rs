@@ -1,38 +1,144 @@
-use std::env;
+use std::{env, path::PathBuf};
 
-#[cfg(feature = "vendored")]
-use std::{
-    io::{Error, ErrorKind},
-    path::PathBuf,
-    process::Output,
-};
+#[cfg(not(feature = "dynamic-loading"))]
+use std::path::Path;
+
+const VERSION: &str = "0.7.0";
 
-const VERSION: &str = "0.6.1";
+/// Minor versions of libjxl with a `jxl_0_<minor>` cfg, set when at least that version is linked
+const CFG_VERSIONS: &[u32] = &[7, 8];
+
+/// The libjxl being linked
+struct Library {
+    /// Directories with the `jxl` headers, empty for the default search paths
+    include_paths: Vec<PathBuf>,
+    /// Directory with the libraries, if known
+    lib_path: Option<PathBuf>,
+    /// Installation prefix, if known
+    root_path: Option<PathBuf>,
+    /// Version as `major.minor.patch`
+    version: String,
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setup_jpegxl()?;
+    let library = setup_jpegxl()?;
+    emit_version_cfgs(&library.version)?;
+    emit_metadata(&library)?;
+
+    #[cfg(feature = "bindgen")]
+    generate_bindings(&library.include_paths)?;
+
+    #[cfg(feature = "layout-tests")]
+    generate_layout_bindings(&library.include_paths)?;
 
     Ok(())
 }
 
-fn setup_jpegxl() -> Result<(), Box<dyn std::error::Error>> {
-    #[cfg(not(feature = "vendored"))]
+#[cfg(all(
+    feature = "dynamic-loading",
+    any(feature = "vendored", feature = "bindgen")
+))]
+compile_error!("The `dynamic-loading` feature can't be combined with `vendored` or `bindgen`");
+
+#[cfg(all(feature = "dynamic-loading", feature = "static"))]
+compile_error!("The `dynamic-loading` feature can't be combined with `static`");
+
+/// Link libjxl
+fn setup_jpegxl() -> Result<Library, Box<dyn std::error::Error>> {
+    #[cfg(feature = "dynamic-loading")]
     {
+        // Nothing is linked, the version of an installed libjxl selects the bindings
+        let library = pkg_config::Config::new()
+            .cargo_metadata(false)
+            .probe("libjxl")
+            .ok();
+
+        Ok(library.map_or_else(
+            || Library {
+                include_paths: Vec::new(),
+                lib_path: None,
+                root_path: None,
+                version: VERSION.to_owned(),
+            },
+            pkg_config_library,
+        ))
+    }
+
+    #[cfg(not(any(feature = "vendored", feature = "dynamic-loading")))]
+    {
+        let statik = cfg!(feature = "static") || env_flag("JXL_STATIC");
+
+        println!("cargo:rerun-if-env-changed=DEP_JXL_LIB");
         if let Ok(path) = env::var("DEP_JXL_LIB") {
             println!("cargo:rustc-link-search=native={}", path);
-            println!("cargo:rustc-link-lib=jxl");
-            #[cfg(feature = "threads")]
-            println!("cargo:rustc-link-lib=jxl_threads");
-            Ok(())
+            if statik {
+                link_static_dir(&PathBuf::from(&path))?;
+            } else {
+                println!("cargo:rustc-link-lib=jxl");
+                #[cfg(feature = "threads")]
+                println!("cargo:rustc-link-lib=jxl_threads");
+            }
+
+            // Headers are expected in the default search paths, or next to the library
+            let search_paths = [
+                PathBuf::from(&path).join("../include"),
+                PathBuf::from("/usr/local/include"),
+                PathBuf::from("/usr/include"),
+            ];
+            let header = header_version(&search_paths);
+            // Set explicitly when the headers are elsewhere, the wrong bindings would be unsound
+            println!("cargo:rerun-if-env-changed=DEP_JXL_VERSION");
+            let version = match (env::var("DEP_JXL_VERSION"), &header) {
+                (Ok(version), _) => version,
+                (Err(_), Some((_, version))) => version.clone(),
+                (Err(_), None) => {
+                    return Err(
+                        "Could not find jxl/version.h next to DEP_JXL_LIB or in the \
+                        default include paths, set DEP_JXL_VERSION to the version of libjxl"
+                            .into(),
+                    )
+                }
+            };
+            let include_paths = header
+                .map(|(include_path, _)| vec![include_path])
+                .unwrap_or_default();
+
+            // The prefix is only known from where the headers are
+            let root_path = include_paths
+                .first()
+                .and_then(|include_path| include_path.parent())
+                .map(Path::to_path_buf);
+
+            Ok(Library {
+                include_paths,
+                lib_path: Some(PathBuf::from(path)),
+                root_path,
+                version,
+            })
         } else {
-            pkg_config::Config::new()
+            // Static resolution also lists the private dependencies, such as brotli and hwy, which
+            // are linked below as pkg-config links libraries in system directories dynamically
+            let library = pkg_config::Config::new()
                 .atleast_version(VERSION)
+                .statik(statik)
+                .cargo_metadata(!statik)
                 .probe("libjxl")?;
             #[cfg(feature = "threads")]
-            pkg_config::Config::new()
+            let threads = pkg_config::Config::new()
                 .atleast_version(VERSION)
+                .statik(statik)
+                .cargo_metadata(!statik)
                 .probe("libjxl_threads")?;
-            Ok(())
+
+            if statik {
+                #[cfg(not(feature = "threads"))]
+                let libraries = [&library];
+                #[cfg(feature = "threads")]
+                let libraries = [&library, &threads];
+                link_static_pkg_config(&libraries)?;
+            }
+
+            Ok(pkg_config_library(library))
         }
     }
 
@@ -40,57 +146,237 @@ fn setup_jpegxl() -> Result<(), Box<dyn std::error::Error>> {
     build()
 }
 
-#[cfg(feature = "vendored")]
-fn check_status(msg: &'static str) -> impl Fn(Output) -> Result<(), Error> {
-    move |e| {
-        e.status.success().then(|| ()).ok_or_else(|| {
-            Error::new(
-                ErrorKind::Other,
-                format!("{}, stderr: {}", msg, String::from_utf8_lossy(&e.stderr)),
+/// The libjxl found by pkg-config, whose `-I` and `-L` flags leave out the system directories,
+/// unlike the variables of `libjxl.pc`
+#[cfg(not(feature = "vendored"))]
+fn pkg_config_library(library: pkg_config::Library) -> Library {
+    let variable = |name| {
+        pkg_config::get_variable("libjxl", name)
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+
+    let mut include_paths = library.include_paths;
+    if let Some(include_path) = variable("includedir") {
+        if !include_paths.contains(&include_path) {
+            include_paths.push(include_path);
+        }
+    }
+
+    Library {
+        include_paths,
+        lib_path: variable("libdir").or_else(|| library.link_paths.into_iter().next()),
+        root_path: variable("prefix"),
+        version: library.version,
+    }
+}
+
+/// Libraries of a static libjxl that must not be linked dynamically
+#[cfg(not(any(feature = "vendored", feature = "dynamic-loading")))]
+fn is_jxl_dependency(name: &str) -> bool {
+    matches!(name, "jxl" | "jxl_threads" | "hwy" | "hwy_contrib") || name.starts_with("brotli")
+}
+
+/// Name of the static library `name` in one of `dirs`, also trying the `-static` suffix of the
+/// brotli libraries built by libjxl
+#[cfg(not(any(feature = "vendored", feature = "dynamic-loading")))]
+fn find_static(dirs: &[PathBuf], name: &str) -> Option<String> {
+    [name.to_owned(), format!("{}-static", name)]
+        .into_iter()
+        .find(|name| {
+            dirs.iter()
+                .any(|dir| dir.join(format!("lib{}.a", name)).exists())
+        })
+}
+
+/// Link the static libraries, and their dependencies, that pkg-config resolved
+#[cfg(not(any(feature = "vendored", feature = "dynamic-loading")))]
+fn link_static_pkg_config(libraries: &[&pkg_config::Library]) -> Result<(), String> {
+    // pkg-config leaves out the system directories
+    let libdir = pkg_config::get_variable("libjxl", "libdir")
+        .ok()
+        .map(PathBuf::from);
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for dir in libraries
+        .iter()
+        .flat_map(|library| &library.link_paths)
+        .chain(&libdir)
+    {
+        if !dirs.contains(dir) {
+            println!("cargo:rustc-link-search=native={}", dir.display());
+            dirs.push(dir.clone());
+        }
+    }
+
+    // Keep the last of duplicates, after the libraries needing them
+    let libs: Vec<_> = libraries.iter().flat_map(|library| &library.libs).collect();
+    let libs = libs
+        .iter()
+        .enumerate()
+        .filter(|&(i, lib)| !libs[i + 1..].contains(lib))
+        .map(|(_, lib)| lib);
+    let mut cxx_runtime_linked = false;
+    for lib in libs {
+        if is_jxl_dependency(lib) {
+            let name = find_static(&dirs, lib).ok_or_else(|| {
+                format!(
+                    "No static lib{}.a in {:?}, needed to link libjxl statically",
+                    lib, dirs
+                )
+            })?;
+            println!("cargo:rustc-link-lib=static={}", name);
+        } else {
+            cxx_runtime_linked |= *lib == "stdc++" || *lib == "c++";
+            println!("cargo:rustc-link-lib={}", lib);
+        }
+    }
+
+    // Not every libjxl.pc lists the C++ runtime
+    if !cxx_runtime_linked {
+        println!("cargo:rustc-link-lib={}", cxx_runtime());
+    }
+
+    Ok(())
+}
+
+/// Link the static libjxl in `dir` and its dependencies, expected in the same directory
+#[cfg(not(any(feature = "vendored", feature = "dynamic-loading")))]
+fn link_static_dir(dir: &Path) -> Result<(), String> {
+    let dirs = [dir.to_owned()];
+    let required = [
+        "jxl",
+        #[cfg(feature = "threads")]
+        "jxl_threads",
+        "hwy",
+        "brotlienc",
+        "brotlidec",
+        "brotlicommon",
+    ];
+    for lib in required {
+        let name = find_static(&dirs, lib).ok_or_else(|| {
+            format!(
+                "No static lib{}.a in DEP_JXL_LIB {}, needed to link libjxl statically",
+                lib,
+                dir.display()
             )
+        })?;
+        println!("cargo:rustc-link-lib=static={}", name);
+    }
+    // Depending on how libjxl was built
+    for lib in ["hwy_contrib", "lcms2"] {
+        if let Some(name) = find_static(&dirs, lib) {
+            println!("cargo:rustc-link-lib=static={}", name);
+        }
+    }
+    println!("cargo:rustc-link-lib={}", cxx_runtime());
+
+    Ok(())
+}
+
+/// Read the version of libjxl from `jxl/version.h` in the first include path that has it, along
+/// with that path
+#[cfg(not(any(feature = "vendored", feature = "dynamic-loading")))]
+fn header_version(include_paths: &[PathBuf]) -> Option<(PathBuf, String)> {
+    let (include_path, header) = include_paths.iter().find_map(|path| {
+        let header = std::fs::read_to_string(path.join("jxl/version.h")).ok()?;
+        Some((path.clone(), header))
+    })?;
+    let define = |name: &str| {
+        header.lines().find_map(|line| {
+            line.strip_prefix("#define ")?
+                .strip_prefix(name)?
+                .trim()
+                .parse::<u32>()
+                .ok()
         })
+    };
+
+    let version = format!(
+        "{}.{}.{}",
+        define("JPEGXL_MAJOR_VERSION")?,
+        define("JPEGXL_MINOR_VERSION")?,
+        define("JPEGXL_PATCH_VERSION")?
+    );
+    Some((include_path, version))
+}
+
+/// Publish the headers, libraries and version to dependent build scripts as `DEP_JXL_*`
+fn emit_metadata(library: &Library) -> Result<(), Box<dyn std::error::Error>> {
+    if !library.include_paths.is_empty() {
+        // Separated like `PATH`, for `env::split_paths`
+        let include = env::join_paths(&library.include_paths)?;
+        println!("cargo:include={}", include.to_string_lossy());
+    }
+    if let Some(lib_path) = &library.lib_path {
+        println!("cargo:lib={}", lib_path.display());
+    }
+    if let Some(root_path) = &library.root_path {
+        println!("cargo:root={}", root_path.display());
+    }
+    println!("cargo:version={}", library.version);
+
+    Ok(())
+}
+
+/// Emit a `jxl_0_<minor>` cfg for each supported version up to the linked one
+fn emit_version_cfgs(version: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut numbers = version.split('.').map(str::parse::<u32>);
+    let (major, minor) = match (numbers.next(), numbers.next()) {
+        (Some(Ok(major)), Some(Ok(minor))) => (major, minor),
+        _ => return Err(format!("Invalid libjxl version: {}", version).into()),
+    };
+    if (major, minor) < (0, 7) {
+        return Err(format!("libjxl {} is too old, {} is required", version, VERSION).into());
+    }
+
+    for &cfg_minor in CFG_VERSIONS {
+        println!("cargo:rustc-check-cfg=cfg(jxl_0_{})", cfg_minor);
+        if major > 0 || minor >= cfg_minor {
+            println!("cargo:rustc-cfg=jxl_0_{}", cfg_minor);
+        }
+    }
+
+    let latest = CFG_VERSIONS[CFG_VERSIONS.len() - 1];
+    if major > 0 || minor > latest {
+        println!(
+            "cargo:warning=libjxl {} is newer than 0.{}, the latest version the bindings know",
+            version, latest
+        );
+    }
+
+    Ok(())
+}
+
+/// The C++ runtime of the target, which a static libjxl needs
+#[cfg(not(feature = "dynamic-loading"))]
+fn cxx_runtime() -> &'static str {
+    match env::var("CARGO_CFG_TARGET_OS").as_deref() {
+        Ok("macos" | "ios" | "freebsd" | "openbsd") => "c++",
+        _ => "stdc++",
     }
 }
 
+/// Whether an environment variable is set to `1`, `ON` or `true`
+#[cfg(not(feature = "dynamic-loading"))]
+fn env_flag(name: &str) -> bool {
+    println!("cargo:rerun-if-env-changed={}", name);
+    env::var(name)
+        .is_ok_and(|value| matches!(value.to_ascii_lowercase().as_str(), "1" | "on" | "true"))
+}
+
 #[cfg(feature = "vendored")]
-fn build() -> Result<(), Box<dyn std::error::Error>> {
+fn build() -> Result<Library, Box<dyn std::error::Error>> {
     use cmake::Config;
-    use std::process::Command;
 
-    let source: PathBuf = env::var("DEP_JXL_PATH")
-        .unwrap_or_else(|_| {
-            format!(
-                "{}/{}",
-                &env::var("OUT_DIR").expect("Failed to get OUT_DIR. Not running under cargo?"),
-                "libjxl"
-            )
-        })
-        .into();
-    let source_str = source.to_str().ok_or("Source path is invalid UTF-8")?;
-
-    if !source.exists() {
-        Command::new("git")
-            .args(&[
-                "clone",
-                "--depth=1",
-                &format!("--branch=v{}", VERSION),
-                "https://github.com/libjxl/libjxl.git",
-                source_str,
-            ])
-            .output()
-            .and_then(check_status("Failed to clone libjxl"))?;
-    }
-    Command::new("git")
-        .args(&["-C", source_str, "submodule", "init"])
-        .output()
-        .and_then(check_status("Failed to init submodule!"))?;
-    Command::new("git")
-        .args(&["-C", source_str, "submodule", "update", "--depth=1"])
-        .output()
-        .and_then(check_status("Failed to update submodule!"))?;
+    println!("cargo:rerun-if-env-changed=DEP_JXL_PATH");
+    let source = env::var_os("DEP_JXL_PATH").map_or_else(jpegxl_src::source_dir, PathBuf::from);
+    check_source(&source)?;
 
     env::set_var("CMAKE_BUILD_PARALLEL_LEVEL", format!("{}", num_cpus::get()));
 
+    let shared = env_flag("JXL_SHARED");
+
     let mut config = Config::new(&source);
     config
         .define("JPEGXL_ENABLE_TOOLS", "OFF")
@@ -100,36 +386,274 @@ fn build() -> Result<(), Box<dyn std::error::Error>> {
         .define("JPEGXL_ENABLE_JNI", "OFF")
         .define("JPEGXL_ENABLE_SJPEG", "OFF")
         .define("JPEGXL_ENABLE_OPENEXR", "OFF")
-        .define("JPEGXL_STATIC", "ON");
+        .define(
+            "JPEGXL_ENABLE_SKCMS",
+            if cfg!(feature = "lcms") { "OFF" } else { "ON" },
+        )
+        .define("JPEGXL_STATIC", if shared { "OFF" } else { "ON" })
+        .define("BUILD_SHARED_LIBS", if shared { "ON" } else { "OFF" })
+        .define("BUILD_TESTING", "OFF");
 
-    let mut prefix = config.build();
-    println!("cargo:rustc-link-lib=static=jxl");
+    println!("cargo:rerun-if-env-changed=JXL_HWY_TARGETS");
+    if let Ok(targets) = env::var("JXL_HWY_TARGETS") {
+        let detect_targets = source.join("third_party/highway/hwy/detect_targets.h");
+        let header = std::fs::read_to_string(&detect_targets)
+            .map_err(|e| format!("Cannot read {}: {}", detect_targets.display(), e))?;
+        jpegxl_src::check_hwy_targets(&header)
+            .map_err(|e| format!("{}, JXL_HWY_TARGETS can't be used with it", e))?;
 
-    #[cfg(feature = "threads")]
-    println!("cargo:rustc-link-lib=static=jxl_threads");
+        // A number, as the flag goes through the shell
+        let disabled = jpegxl_src::hwy_disabled_targets(&targets)
+            .map_err(|e| format!("{} in JXL_HWY_TARGETS", e))?;
+        config.cxxflag(format!("-DHWY_DISABLED_TARGETS={}", disabled));
+    }
+
+    // Last, to override any of the above
+    println!("cargo:rerun-if-env-changed=JXL_CMAKE_DEFINES");
+    if let Ok(defines) = env::var("JXL_CMAKE_DEFINES") {
+        for define in defines.split_whitespace() {
+            let (name, value) = define.split_once('=').ok_or_else(|| {
+                format!(
+                    "Invalid define `{}` in JXL_CMAKE_DEFINES, expected `NAME=VALUE`",
+                    define
+                )
+            })?;
+            config.define(name, value);
+        }
+    }
 
-    println!("cargo:rustc-link-lib=static=hwy");
-    prefix.push("lib");
-    println!("cargo:rustc-link-search=native={}", prefix.display());
-    prefix.pop();
+    let prefix = config.build();
+    let include_path = prefix.join("include");
+    let lib_path = prefix.join("lib");
+    let third_party_path = prefix.join("build").join("third_party");
+    println!("cargo:rustc-link-search=native={}", lib_path.display());
+    println!(
+        "cargo:rustc-link-search=native={}",
+        third_party_path.display()
+    );
+    println!(
+        "cargo:rustc-link-search=native={}",
+        third_party_path.join("brotli").display()
+    );
+
+    if shared {
+        // The shared libraries link their dependencies themselves
+        println!("cargo:rustc-link-lib=dylib=jxl");
+        #[cfg(feature = "threads")]
+        println!("cargo:rustc-link-lib=dylib=jxl_threads");
+    } else {
+        println!("cargo:rustc-link-lib=static=jxl");
+        #[cfg(feature = "threads")]
+        println!("cargo:rustc-link-lib=static=jxl_threads");
+
+        println!("cargo:rustc-link-lib=static=hwy");
+        // Built unless disabled with `HWY_ENABLE_CONTRIB=OFF`
+        if std::fs::read_dir(&lib_path)?
+            .filter_map(Result::ok)
+            .any(|entry| entry.file_name().to_string_lossy().contains("hwy_contrib"))
+        {
+            println!("cargo:rustc-link-lib=static=hwy_contrib");
+        }
 
-    prefix.push("build");
-    prefix.push("third_party");
-    println!("cargo:rustc-link-search=native={}", prefix.display());
+        #[cfg(feature = "lcms")]
+        println!("cargo:rustc-link-lib=static=lcms2");
 
-    println!("cargo:rustc-link-lib=static=brotlicommon-static");
-    println!("cargo:rustc-link-lib=static=brotlidec-static");
-    println!("cargo:rustc-link-lib=static=brotlienc-static");
-    prefix.push("brotli");
-    println!("cargo:rustc-link-search=native={}", prefix.display());
+        println!("cargo:rustc-link-lib=static=brotlicommon-static");
+        println!("cargo:rustc-link-lib=static=brotlidec-static");
+        println!("cargo:rustc-link-lib=static=brotlienc-static");
 
+        #[cfg(feature = "threads")]
+        println!("cargo:rustc-link-lib={}", cxx_runtime());
+    }
+
+    Ok(Library {
+        include_paths: vec![include_path],
+        lib_path: Some(lib_path),
+        root_path: Some(prefix),
+        version: jpegxl_src::VERSION.to_owned(),
+    })
+}
+
+/// Check the libjxl sources are complete, as the build never fetches them
+#[cfg(feature = "vendored")]
+fn check_source(source: &Path) -> Result<(), String> {
+    let problem = if source.join("CMakeLists.txt").exists() {
+        // Only one of the color management systems is built
+        let unused = if cfg!(feature = "lcms") {
+            "third_party/skcms"
+        } else {
+            "third_party/lcms"
+        };
+        let missing: Vec<_> = jpegxl_src::SUBMODULES
+            .iter()
+            .filter(|&&dir| dir != unused)
+            .filter(|&&dir| {
+                // Little CMS is built by a CMake file of libjxl
+                let file = if dir == "third_party/lcms" {
+                    "include/lcms2.h"
+                } else {
+                    "CMakeLists.txt"
+                };
+                !source.join(dir).join(file).exists()
+            })
+            .copied()
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        format!(
+            "The libjxl sources in {} lack the submodules {}",
+            source.display(),
+            missing.join(", ")
+        )
+    } else {
+        format!("No libjxl sources in {}", source.display())
+    };
+
+    Err(format!(
+        "{}. The vendored build never accesses the network: run `jpegxl-src/update.sh` in a \
+         checkout of jpegxl-sys, or set DEP_JXL_PATH to a checkout of libjxl v{} with its \
+         submodules, from `git clone --recursive --shallow-submodules --depth=1 --branch=v{} \
+         https://github.com/libjxl/libjxl.git`",
+        problem,
+        jpegxl_src::VERSION,
+        jpegxl_src::VERSION
+    ))
+}
+
+/// Start a `bindgen` builder for the libjxl headers included by `contents`
+#[cfg(any(feature = "bindgen", feature = "layout-tests"))]
+fn bindgen_builder(include_paths: &[PathBuf], contents: &str) -> bindgen::Builder {
+    for path in include_paths {
+        println!("cargo:rerun-if-changed={}", path.join("jxl").display());
+    }
+
+    bindgen::Builder::default()
+        .header_contents("jxl.h", contents)
+        .clang_args(
+            include_paths
+                .iter()
+                .map(|path| format!("-I{}", path.display())),
+        )
+        .layout_tests(false)
+        .generate_comments(false)
+}
+
+/// Modules of the bindings with the header declaring their functions
+#[cfg(feature = "bindgen")]
+const MODULE_HEADERS: &[(&str, &str)] = &[
+    ("butteraugli", "butteraugli.h"),
+    ("decoder", "decode.h"),
+    ("encoder", "encode.h"),
     #[cfg(feature = "threads")]
-    {
-        #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
-        println!("cargo:rustc-link-lib=c++");
-        #[cfg(not(any(target_os = "macos", target_os = "ios", target_os = "freebsd")))]
-        println!("cargo:rustc-link-lib=stdc++");
+    ("parallel_runner", "thread_parallel_runner.h"),
+    #[cfg(feature = "threads")]
+    ("resizable_parallel_runner", "resizable_parallel_runner.h"),
+];
+
+/// Types defined by hand, which the generated functions use instead
+#[cfg(feature = "bindgen")]
+const HAND_WRITTEN_TYPES: &[&str] = &[
+    "Jxl.*Struct",
+    "JxlAnimationHeader",
+    "JxlBasicInfo",
+    "JxlBitDepth",
+    "JxlBitDepthType",
+    "JxlBlendInfo",
+    "JxlBlendMode",
+    "JxlBoxType",
+    "JxlButteraugliApi",
+    "JxlButteraugliResult",
+    "JxlColorEncoding",
+    "JxlColorProfileTarget",
+    "JxlColorSpace",
+    "JxlDataType",
+    "JxlDecoder",
+    "JxlDecoderStatus",
+    "JxlEncoder",
+    "JxlEncoderFrameSettingId",
+    "JxlEncoderFrameSettings",
+    "JxlEncoderOptions",
+    "JxlEncoderStatus",
+    "JxlEndianness",
+    "JxlExtraChannelInfo",
+    "JxlExtraChannelType",
+    "JxlFrameHeader",
+    "JxlHeaderExtensions",
+    "JxlImageOut.*Callback",
+    "JxlLayerInfo",
+    "JxlMemoryManager",
+    "JxlOrientation",
+    "JxlParallelRetCode",
+    "JxlParallelRunFunction",
+    "JxlParallelRunInit",
+    "JxlParallelRunner",
+    "JxlPixelFormat",
+    "JxlPreviewHeader",
+    "JxlPrimaries",
+    "JxlProgressiveDetail",
+    "JxlRenderingIntent",
+    "JxlSignature",
+    "JxlTransferFunction",
+    "JxlWhitePoint",
+];
+
+/// Functions declared by hand with more precise types than the headers
+#[cfg(feature = "bindgen")]
+const HAND_WRITTEN_FUNCTIONS: &[&str] = &[
+    "JxlDecoderSubscribeEvents",
+    "JxlDecoderGetBoxType",
+    "JxlEncoderAddBox",
+    "JxlButteraugliResultGetDistmap",
+];
+
+/// Generate the functions of each module from the libjxl headers, using the hand-written types
+#[cfg(feature = "bindgen")]
+fn generate_bindings(include_paths: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?).join("bindings");
+    std::fs::create_dir_all(&out_dir)?;
+
+    for (module, header) in MODULE_HEADERS {
+        // The hand-written functions take `bool` for `JXL_BOOL`
+        let contents = format!(
+            "#include <jxl/types.h>\n#undef JXL_BOOL\n#define JXL_BOOL _Bool\n#include <jxl/{}>\n",
+            header
+        );
+        let mut builder = bindgen_builder(include_paths, &contents)
+            .allowlist_file(format!(".*/jxl/{}", header.replace('.', "\\.")))
+            .size_t_is_usize(true)
+            .raw_line("#[allow(unused_imports)]")
+            .raw_line("use crate::common::*;");
+        for type_ in HAND_WRITTEN_TYPES {
+            builder = builder.blocklist_type(type_);
+        }
+        for function in HAND_WRITTEN_FUNCTIONS {
+            builder = builder.blocklist_function(function);
+        }
+
+        builder
+            .generate()
+            .map_err(|_| format!("Failed to generate bindings from jxl/{}", header))?
+            .write_to_file(out_dir.join(module).with_extension("rs"))?;
     }
 
     Ok(())
 }
+
+/// Generate declarations from the libjxl headers to check the layout of the hand-written bindings
+#[cfg(feature = "layout-tests")]
+fn generate_layout_bindings(include_paths: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+    let out_path = PathBuf::from(env::var("OUT_DIR")?).join("layout_bindings.rs");
+
+    bindgen_builder(
+        include_paths,
+        "#include <jxl/butteraugli.h>\n#include <jxl/decode.h>\n#include <jxl/encode.h>\n#include <jxl/version.h>\n",
+    )
+    .allowlist_type("Jxl.*")
+    .allowlist_var("JPEGXL_.*|JXL_.*")
+    .generate()
+        .map_err(|_| "Failed to generate bindings from the libjxl headers")?
+        .write_to_file(out_path)?;
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Sources of libjxl with the submodules needed to build it, for the `vendored` feature of
+//! `jpegxl-sys`.
+//!
+//! The `libjxl` directory is filled by `update.sh` before publishing, so building from this crate
+//! never needs network access.
+
+#![warn(clippy::pedantic)]
+
+use std::path::{Path, PathBuf};
+
+/// Version of the bundled libjxl
+pub const VERSION: &str = "0.7.0";
+
+/// Bundled submodules of libjxl, relative to the source directory. The build needs brotli,
+/// highway, and either lcms or skcms.
+pub const SUBMODULES: &[&str] = &[
+    "third_party/brotli",
+    "third_party/highway",
+    "third_party/lcms",
+    "third_party/skcms",
+];
+
+/// x86 targets of the bundled Highway, with their bits in `HWY_DISABLED_TARGETS`
+pub const HWY_X86_TARGETS: &[(&str, u64)] = &[
+    ("AVX3_DL", 1 << 7),
+    ("AVX3", 1 << 8),
+    ("AVX2", 1 << 9),
+    ("SSE4", 1 << 11),
+    ("SSSE3", 1 << 12),
+];
+
+/// Value of `HWY_DISABLED_TARGETS` disabling the x86 targets of [`HWY_X86_TARGETS`] that are not
+/// in `targets`, whose names are separated by spaces or commas
+///
+/// # Errors
+/// Returns an error if a name is not in [`HWY_X86_TARGETS`]
+pub fn hwy_disabled_targets(targets: &str) -> Result<u64, String> {
+    let mut enabled = 0;
+    for target in targets
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|target| !target.is_empty())
+    {
+        let bit = HWY_X86_TARGETS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(target))
+            .ok_or_else(|| {
+                let names: Vec<_> = HWY_X86_TARGETS.iter().map(|(name, _)| *name).collect();
+                format!(
+                    "Unknown Highway target `{}`, expected some of {}",
+                    target,
+                    names.join(", ")
+                )
+            })?
+            .1;
+        enabled |= bit;
+    }
+
+    Ok(HWY_X86_TARGETS
+        .iter()
+        .map(|(_, bit)| bit)
+        .filter(|&&bit| enabled & bit == 0)
+        .fold(0, |disabled, bit| disabled | bit))
+}
+
+/// Check [`HWY_X86_TARGETS`] against the content of `hwy/detect_targets.h` of the Highway being
+/// built, as Highway renumbers its targets between releases
+///
+/// # Errors
+/// Returns an error if a target is not defined, or with another bit
+pub fn check_hwy_targets(detect_targets: &str) -> Result<(), String> {
+    for &(name, bit) in HWY_X86_TARGETS {
+        let define = format!("HWY_{name}");
+        let value = detect_targets
+            .lines()
+            .find_map(|line| {
+                let rest = line.trim().strip_prefix("#define")?.trim_start();
+                let value = rest.strip_prefix(&define)?;
+                // Not a longer name, such as `HWY_AVX3_DL` for `HWY_AVX3`
+                value.starts_with(char::is_whitespace).then_some(value)
+            })
+            .ok_or_else(|| format!("Highway does not define {define}"))?;
+        let value = value.split("//").next().unwrap_or_default();
+        let value = value.trim().trim_start_matches('(').trim_end_matches(')');
+
+        let parsed = match value.split_once("<<") {
+            Some((one, shift)) if one.trim().trim_end_matches(['L', 'U']) == "1" => shift
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .and_then(|shift| 1u64.checked_shl(shift)),
+            Some(_) => None,
+            None => value.trim_end_matches(['L', 'U']).parse().ok(),
+        };
+        if parsed != Some(bit) {
+            return Err(format!(
+                "Highway defines {define} as `{value}`, expected {bit}"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Directory with the bundled libjxl sources
+#[must_use]
+pub fn source_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("libjxl")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hwy_disabled_targets() {
+        assert_eq!(hwy_disabled_targets("SSSE3,SSE4,AVX2,AVX3,AVX3_DL"), Ok(0));
+        assert_eq!(
+            hwy_disabled_targets("avx2 sse4, SSSE3"),
+            Ok((1 << 7) | (1 << 8))
+        );
+        assert_eq!(hwy_disabled_targets("SSE4"), Ok(0x1380));
+        assert_eq!(hwy_disabled_targets(""), Ok(0x1b80));
+        assert!(hwy_disabled_targets("AVX2,NEON")
+            .unwrap_err()
+            .contains("`NEON`"));
+    }
+
+    #[test]
+    fn test_check_hwy_targets() {
+        let header = "\
+#define HWY_AVX3_SPR (1LL << 4)
+#define HWY_AVX3_DL (1LL << 7)  // opt-in for dynamic dispatch
+#define HWY_AVX3 (1LL << 8)
+#define HWY_AVX2 (1LL << 9)
+// Bit 10: reserved for AVX
+#define HWY_SSE4 (1LL << 11)
+#define HWY_SSSE3 4096
+";
+        assert_eq!(check_hwy_targets(header), Ok(()));
+        assert!(check_hwy_targets(&header.replace("<< 9", "<< 10"))
+            .unwrap_err()
+            .contains("HWY_AVX2"));
+        assert!(check_hwy_targets(&header.replace("HWY_SSE4 ", "HWY_SSE4_"))
+            .unwrap_err()
+            .contains("does not define HWY_SSE4"));
+    }
+
+    #[test]
+    fn test_bundled_hwy_targets() {
+        let path = source_dir().join("third_party/highway/hwy/detect_targets.h");
+        // Only in a checkout with the sources, filled by `update.sh`
+        if let Ok(header) = std::fs::read_to_string(path) {
+            assert_eq!(check_hwy_targets(&header), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,18 @@
+#!/bin/sh
+# Bundle the sources of libjxl, with the submodules in `SUBMODULES` of src/lib.rs, into `libjxl`.
+# This needs network access: run it before publishing, the build never does.
+set -eu
+
+cd "$(dirname "$0")"
+version=$(sed -n 's/^pub const VERSION: &str = "\(.*\)";$/\1/p' src/lib.rs)
+submodules=$(sed -n '/^pub const SUBMODULES/,/^];$/s/^ *"\(.*\)",$/\1/p' src/lib.rs)
+
+rm -rf libjxl
+git clone --depth=1 --branch "v$version" https://github.com/libjxl/libjxl.git libjxl
+for submodule in $submodules; do
+    git -C libjxl submodule update --init --depth=1 "$submodule"
+done
+
+# Keep only the sources
+find libjxl -name .git -prune -exec rm -rf {} +
+rm -rf libjxl/testdata libjxl/third_party/highway/g3doc libjxl/third_party/brotli/tests
@@ -15,6 +15,9 @@ You should have received a copy of the GNU General Public License
 along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+// The generated functions name the C types by their full path
+#![cfg_attr(feature = "bindgen", allow(unused_imports))]
+
 use std::ffi::c_void;
 
 use crate::common::{JxlMemoryManager, JxlParallelRunner, JxlPixelFormat};
@@ -31,44 +34,59 @@ pub struct JxlButteraugliResult {
     _unused: [u8; 0],
 }
 
-extern "C" {
-    pub fn JxlButteraugliResultDestroy(result: *mut JxlButteraugliResult);
+#[cfg(feature = "bindgen")]
+include!(concat!(env!("OUT_DIR"), "/bindings/butteraugli.rs"));
+
+extern_functions! {
+    #[cfg(not(feature = "bindgen"))]
+    mod functions;
 
-    pub fn JxlButteraugliApiCreate(
-        memory_manager: *const JxlMemoryManager,
-    ) -> *mut JxlButteraugliApi;
+    extern "C" {
+        pub fn JxlButteraugliResultDestroy(result: *mut JxlButteraugliResult);
 
-    pub fn JxlButteraugliApiSetParallelRunner(
-        api: *mut JxlButteraugliApi,
-        parallel_runner: JxlParallelRunner,
-        parallel_runner_opaque: *mut c_void,
-    );
+        pub fn JxlButteraugliApiCreate(
+            memory_manager: *const JxlMemoryManager,
+        ) -> *mut JxlButteraugliApi;
 
-    pub fn JxlButteraugliApiSetHFAsymmetry(api: *mut JxlButteraugliApi, v: f32);
+        pub fn JxlButteraugliApiSetParallelRunner(
+            api: *mut JxlButteraugliApi,
+            parallel_runner: JxlParallelRunner,
+            parallel_runner_opaque: *mut c_void,
+        );
 
-    pub fn JxlButteraugliApiSetIntensityTarget(api: *mut JxlButteraugliApi, v: f32);
+        pub fn JxlButteraugliApiSetHFAsymmetry(api: *mut JxlButteraugliApi, v: f32);
 
-    pub fn JxlButteraugliApiDestroy(api: *mut JxlButteraugliApi);
+        pub fn JxlButteraugliApiSetIntensityTarget(api: *mut JxlButteraugliApi, v: f32);
 
-    pub fn JxlButteraugliCompute(
-        api: *const JxlButteraugliApi,
-        xsize: u32,
-        ysize: u32,
-        pixel_format_orig: *const JxlPixelFormat,
-        buffer_orig: *const c_void,
-        size_orig: usize,
-        pixel_format_dist: *const JxlPixelFormat,
-        buffer_dist: *const c_void,
-        size_dist: usize,
-    ) -> *mut JxlButteraugliResult;
+        pub fn JxlButteraugliApiDestroy(api: *mut JxlButteraugliApi);
 
-    pub fn JxlButteraugliResultGetMaxDistance(result: *const JxlButteraugliResult) -> f32;
+        pub fn JxlButteraugliCompute(
+            api: *const JxlButteraugliApi,
+            xsize: u32,
+            ysize: u32,
+            pixel_format_orig: *const JxlPixelFormat,
+            buffer_orig: *const c_void,
+            size_orig: usize,
+            pixel_format_dist: *const JxlPixelFormat,
+            buffer_dist: *const c_void,
+            size_dist: usize,
+        ) -> *mut JxlButteraugliResult;
 
-    pub fn JxlButteraugliResultGetDistance(result: *const JxlButteraugliResult, pnorm: f32) -> f32;
+        pub fn JxlButteraugliResultGetMaxDistance(result: *const JxlButteraugliResult) -> f32;
+
+        pub fn JxlButteraugliResultGetDistance(result: *const JxlButteraugliResult, pnorm: f32) -> f32;
+    }
+}
 
-    pub fn JxlButteraugliResultGetDistmap(
-        result: *const JxlButteraugliResult,
-        buffer: *const *const f32,
-        row_stride: *mut u32,
-    );
+// Declared by hand to keep its signature, even with the `bindgen` feature
+extern_functions! {
+    mod precise_functions;
+
+    extern "C" {
+        pub fn JxlButteraugliResultGetDistmap(
+            result: *const JxlButteraugliResult,
+            buffer: *const *const f32,
+            row_stride: *mut u32,
+        );
+    }
 }
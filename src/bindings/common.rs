@@ -15,21 +15,36 @@ You should have received a copy of the GNU General Public License
 along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use std::{ffi::c_void, os::raw::c_int};
+use std::{
+    ffi::c_void,
+    fmt,
+    os::raw::{c_char, c_int},
+};
+
+/// C `JXL_BOOL`, where any non-zero value is true
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JxlBool(pub c_int);
+
+#[allow(non_upper_case_globals)]
+impl JxlBool {
+    pub const True: Self = Self(1);
+    pub const False: Self = Self(0);
+}
 
-#[repr(i32)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum JxlBool {
-    True = 1,
-    False = 0,
+impl fmt::Debug for JxlBool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::True => f.write_str("True"),
+            Self::False => f.write_str("False"),
+            Self(value) => f.debug_tuple("JxlBool").field(&value).finish(),
+        }
+    }
 }
 
 impl From<JxlBool> for bool {
     fn from(b: JxlBool) -> Self {
-        match b {
-            JxlBool::True => true,
-            JxlBool::False => false,
-        }
+        b.0 != 0
     }
 }
 
@@ -43,23 +58,23 @@ impl From<bool> for JxlBool {
     }
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum JxlDataType {
-    Float = 0,
-    Boolean,
-    Uint8,
-    Uint16,
-    Uint32,
-    Float16,
+ffi_enum! {
+    pub struct JxlDataType => pub enum DataType {
+        Float = 0,
+        Boolean = 1,
+        Uint8 = 2,
+        Uint16 = 3,
+        Uint32 = 4,
+        Float16 = 5,
+    }
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum JxlEndianness {
-    Native = 0,
-    Little,
-    Big,
+ffi_enum! {
+    pub struct JxlEndianness => pub enum Endianness {
+        Native = 0,
+        Little = 1,
+        Big = 2,
+    }
 }
 
 #[repr(C)]
@@ -71,53 +86,74 @@ pub struct JxlPixelFormat {
     pub align: usize,
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum JxlColorSpace {
-    Rgb = 0,
-    Gray,
-    Xyb,
-    Unknown,
+impl JxlPixelFormat {
+    /// Interleaved `num_channels` samples of `data_type` in native endianness, without row
+    /// alignment
+    #[must_use]
+    pub const fn new(num_channels: u32, data_type: JxlDataType) -> Self {
+        Self {
+            num_channels,
+            data_type,
+            endianness: JxlEndianness::Native,
+            align: 0,
+        }
+    }
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum JxlWhitePoint {
-    D65 = 1,
-    Custom = 2,
-    E = 10,
-    Dci = 11,
+/// All zeroes: no channels of `Float`, native endianness and no alignment
+impl Default for JxlPixelFormat {
+    fn default() -> Self {
+        Self::new(0, JxlDataType::Float)
+    }
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum JxlPrimaries {
-    SRgb = 1,
-    Custom = 2,
-    Rec2100 = 9,
-    P3 = 11,
+ffi_enum! {
+    pub struct JxlColorSpace => pub enum ColorSpace {
+        Rgb = 0,
+        Gray = 1,
+        Xyb = 2,
+        Unknown = 3,
+    }
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum JxlTransferFunction {
-    Rec709 = 1,
-    Unknown = 2,
-    Linear = 8,
-    SRgb = 13,
-    Pq = 16,
-    Dci = 17,
-    Hlg = 18,
-    Gamma = 65535,
+ffi_enum! {
+    pub struct JxlWhitePoint => pub enum WhitePoint {
+        D65 = 1,
+        Custom = 2,
+        E = 10,
+        Dci = 11,
+    }
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum JxlRenderingIntent {
-    Perceptual = 0,
-    Relative,
-    Saturation,
-    Absolute,
+ffi_enum! {
+    pub struct JxlPrimaries => pub enum Primaries {
+        SRgb = 1,
+        Custom = 2,
+        Rec2100 = 9,
+        P3 = 11,
+    }
+}
+
+ffi_enum! {
+    pub struct JxlTransferFunction => pub enum TransferFunction {
+        Rec709 = 1,
+        Unknown = 2,
+        Linear = 8,
+        SRgb = 13,
+        Pq = 16,
+        Dci = 17,
+        Hlg = 18,
+        Gamma = 65535,
+    }
+}
+
+ffi_enum! {
+    pub struct JxlRenderingIntent => pub enum RenderingIntent {
+        Perceptual = 0,
+        Relative = 1,
+        Saturation = 2,
+        Absolute = 3,
+    }
 }
 
 #[repr(C)]
@@ -135,39 +171,39 @@ pub struct JxlColorEncoding {
     pub rendering_intent: JxlRenderingIntent,
 }
 
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum JxlOrientation {
-    Identity = 1,
-    FlipHorizontal = 2,
-    Rotate180 = 3,
-    FlipVertical = 4,
-    Transpose = 5,
-    Rotate90Cw = 6,
-    AntiTranspose = 7,
-    Rotate90Ccw = 8,
+ffi_enum! {
+    pub struct JxlOrientation => pub enum Orientation {
+        Identity = 1,
+        FlipHorizontal = 2,
+        Rotate180 = 3,
+        FlipVertical = 4,
+        Transpose = 5,
+        Rotate90Cw = 6,
+        AntiTranspose = 7,
+        Rotate90Ccw = 8,
+    }
 }
 
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum JxlExtraChannelType {
-    Alpha,
-    Depth,
-    SpotColor,
-    SelectionMask,
-    Black,
-    Cfa,
-    Thermal,
-    Reserved0,
-    Reserved1,
-    Reserved2,
-    Reserved3,
-    Reserved4,
-    Reserved5,
-    Reserved6,
-    Reserved7,
-    Unknown,
-    Optional,
+ffi_enum! {
+    pub struct JxlExtraChannelType => pub enum ExtraChannelType {
+        Alpha = 0,
+        Depth = 1,
+        SpotColor = 2,
+        SelectionMask = 3,
+        Black = 4,
+        Cfa = 5,
+        Thermal = 6,
+        Reserved0 = 7,
+        Reserved1 = 8,
+        Reserved2 = 9,
+        Reserved3 = 10,
+        Reserved4 = 11,
+        Reserved5 = 12,
+        Reserved6 = 13,
+        Reserved7 = 14,
+        Unknown = 15,
+        Optional = 16,
+    }
 }
 
 #[repr(C)]
@@ -209,7 +245,9 @@ pub struct JxlBasicInfo {
     pub alpha_premultiplied: JxlBool,
     pub preview: JxlPreviewHeader,
     pub animation: JxlAnimationHeader,
-    _padding: [u8; 108],
+    pub intrinsic_xsize: u32,
+    pub intrinsic_ysize: u32,
+    _padding: [u8; 100],
 }
 
 #[repr(C)]
@@ -231,6 +269,37 @@ pub struct JxlHeaderExtensions {
     pub extensions: u64,
 }
 
+ffi_enum! {
+    pub struct JxlBlendMode => pub enum BlendMode {
+        Replace = 0,
+        Add = 1,
+        Blend = 2,
+        MulAdd = 3,
+        Mul = 4,
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JxlBlendInfo {
+    pub blendmode: JxlBlendMode,
+    pub source: u32,
+    pub alpha: u32,
+    pub clamp: JxlBool,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JxlLayerInfo {
+    pub have_crop: JxlBool,
+    pub crop_x0: i32,
+    pub crop_y0: i32,
+    pub xsize: u32,
+    pub ysize: u32,
+    pub blend_info: JxlBlendInfo,
+    pub save_as_reference: u32,
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct JxlFrameHeader {
@@ -238,6 +307,7 @@ pub struct JxlFrameHeader {
     pub timecode: u32,
     pub name_length: u32,
     pub is_last: JxlBool,
+    pub layer_info: JxlLayerInfo,
 }
 
 pub type JpegxlAllocFunc = unsafe extern "C" fn(opaque: *mut c_void, size: usize) -> *mut c_void;
@@ -253,6 +323,9 @@ pub struct JxlMemoryManager {
 
 pub type JxlParallelRetCode = c_int;
 
+pub const JXL_PARALLEL_RET_SUCCESS: JxlParallelRetCode = 0;
+pub const JXL_PARALLEL_RET_RUNNER_ERROR: JxlParallelRetCode = -1;
+
 pub type JxlParallelRunInit =
     unsafe extern "C" fn(jpegxl_opaque: *mut c_void, num_threads: usize) -> JxlParallelRetCode;
 
@@ -268,13 +341,13 @@ pub type JxlParallelRunner = unsafe extern "C" fn(
     end_range: u32,
 ) -> JxlParallelRetCode;
 
-#[repr(C)]
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub enum JxlSignature {
-    NotEnoughBytes = 0,
-    Invalid = 1,
-    Codestream = 2,
-    Container = 3,
+ffi_enum! {
+    pub struct JxlSignature => pub enum Signature {
+        NotEnoughBytes = 0,
+        Invalid = 1,
+        Codestream = 2,
+        Container = 3,
+    }
 }
 
 #[repr(C)]
@@ -283,3 +356,29 @@ pub enum JxlColorProfileTarget {
     Original,
     Data,
 }
+
+/// Four-character code of an ISOBMFF box, such as `Exif`, `xml ` or `jumb`.
+pub type JxlBoxType = [c_char; 4];
+
+/// How the bit depth of a pixel buffer is interpreted
+#[cfg(jxl_0_8)]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JxlBitDepthType {
+    /// The full range of the data type of the pixel format
+    FromPixelFormat,
+    /// The bit depth in the basic info of the image
+    FromCodestream,
+    /// The bit depth in `bits_per_sample` and `exponent_bits_per_sample`
+    Custom,
+}
+
+/// Bit depth of an input or output pixel buffer
+#[cfg(jxl_0_8)]
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JxlBitDepth {
+    pub type_: JxlBitDepthType,
+    pub bits_per_sample: u32,
+    pub exponent_bits_per_sample: u32,
+}
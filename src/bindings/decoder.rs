@@ -15,16 +15,24 @@ You should have received a copy of the GNU General Public License
 along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+// The generated functions name the C types by their full path
+#![cfg_attr(feature = "bindgen", allow(unused_imports))]
+
 use std::{
     ffi::c_void,
+    ops::{BitOr, BitOrAssign},
     os::raw::{c_char, c_int},
 };
 
 use crate::common::{
-    JxlBasicInfo, JxlColorEncoding, JxlColorProfileTarget, JxlExtraChannelInfo, JxlFrameHeader,
-    JxlMemoryManager, JxlParallelRunner, JxlPixelFormat, JxlSignature,
+    JxlBasicInfo, JxlBlendInfo, JxlBoxType, JxlColorEncoding, JxlColorProfileTarget,
+    JxlExtraChannelInfo, JxlFrameHeader, JxlMemoryManager, JxlParallelRunner, JxlPixelFormat,
+    JxlSignature,
 };
 
+#[cfg(jxl_0_8)]
+use crate::common::JxlBitDepth;
+
 // Opaque type
 #[allow(clippy::module_name_repetitions)]
 #[repr(C)]
@@ -32,33 +40,207 @@ pub struct JxlDecoder {
     _unused: [u8; 0],
 }
 
+ffi_enum! {
+    pub struct JxlDecoderStatus => pub enum DecoderStatus {
+        Success = 0,
+        Error = 1,
+        NeedMoreInput = 2,
+        NeedPreviewOutBuffer = 3,
+        #[cfg(not(jxl_0_8))]
+        NeedDcOutBuffer = 4,
+        NeedImageOutBuffer = 5,
+        JpegNeedMoreOutput = 6,
+        BoxNeedMoreOutput = 7,
+        BasicInfo = 0x40,
+        #[cfg(not(jxl_0_8))]
+        Extensions = 0x80,
+        ColorEncoding = 0x100,
+        PreviewImage = 0x200,
+        Frame = 0x400,
+        #[cfg(not(jxl_0_8))]
+        DcImage = 0x800,
+        FullImage = 0x1000,
+        JpegReconstruction = 0x2000,
+        Box = 0x4000,
+        FrameProgression = 0x8000,
+    }
+}
+
 #[repr(C)]
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub enum JxlDecoderStatus {
-    Success = 0,
-    Error = 1,
-    NeedMoreInput = 2,
-    NeedPreviewOutBuffer = 3,
-    NeedDcOutBuffer = 4,
-    NeedImageOutBuffer = 5,
-    JpegNeedMoreOutput = 6,
-    BasicInfo = 0x40,
-    Extensions = 0x80,
-    ColorEncoding = 0x100,
-    PreviewImage = 0x200,
-    Frame = 0x400,
-    DcImage = 0x800,
-    FullImage = 0x1000,
-    JpegReconstruction = 0x2000,
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum JxlProgressiveDetail {
+    Frames = 0,
+    Dc = 1,
+    LastPasses = 2,
+    Passes = 3,
+    DcProgressive = 4,
+    DcGroups = 5,
+    Groups = 6,
+}
+
+/// Events to subscribe to with `JxlDecoderSubscribeEvents`
+///
+/// Only the statuses of [`JxlDecoderStatus`] that are events can be converted to flags.
+///
+/// `JxlDecoderSubscribeEvents` took the `c_int` flags of the header before this type, and taking
+/// it instead is a semver-major change of the bindings. Raw flags can still be passed with
+/// [`JxlDecoderEvents::from_bits_retain`].
+#[repr(transparent)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct JxlDecoderEvents(c_int);
+
+impl JxlDecoderEvents {
+    pub const BASIC_INFO: Self = Self(JxlDecoderStatus::BasicInfo.0);
+    #[cfg(not(jxl_0_8))]
+    pub const EXTENSIONS: Self = Self(JxlDecoderStatus::Extensions.0);
+    pub const COLOR_ENCODING: Self = Self(JxlDecoderStatus::ColorEncoding.0);
+    pub const PREVIEW_IMAGE: Self = Self(JxlDecoderStatus::PreviewImage.0);
+    pub const FRAME: Self = Self(JxlDecoderStatus::Frame.0);
+    #[cfg(not(jxl_0_8))]
+    pub const DC_IMAGE: Self = Self(JxlDecoderStatus::DcImage.0);
+    pub const FULL_IMAGE: Self = Self(JxlDecoderStatus::FullImage.0);
+    pub const JPEG_RECONSTRUCTION: Self = Self(JxlDecoderStatus::JpegReconstruction.0);
+    pub const BOX: Self = Self(JxlDecoderStatus::Box.0);
+    pub const FRAME_PROGRESSION: Self = Self(JxlDecoderStatus::FrameProgression.0);
+
+    /// No events
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// All events of the supported libjxl version
+    #[must_use]
+    pub const fn all() -> Self {
+        let all = Self::BASIC_INFO.0
+            | Self::COLOR_ENCODING.0
+            | Self::PREVIEW_IMAGE.0
+            | Self::FRAME.0
+            | Self::FULL_IMAGE.0
+            | Self::JPEG_RECONSTRUCTION.0
+            | Self::BOX.0
+            | Self::FRAME_PROGRESSION.0;
+        #[cfg(not(jxl_0_8))]
+        let all = all | Self::EXTENSIONS.0 | Self::DC_IMAGE.0;
+        Self(all)
+    }
+
+    /// Flags from raw `bits`, or `None` if they contain bits that are not events
+    #[must_use]
+    pub const fn from_bits(bits: c_int) -> Option<Self> {
+        if bits & !Self::all().0 == 0 {
+            Some(Self(bits))
+        } else {
+            None
+        }
+    }
+
+    /// Flags from raw `bits`, kept as they are for libjxl to check, as the `c_int` flags once
+    /// passed to `JxlDecoderSubscribeEvents`
+    #[must_use]
+    pub const fn from_bits_retain(bits: c_int) -> Self {
+        Self(bits)
+    }
+
+    /// Flag of an event status
+    ///
+    /// # Panics
+    /// Panics if `status` is not an event, which is a compile error in a constant context
+    #[must_use]
+    pub const fn from_status(status: JxlDecoderStatus) -> Self {
+        match status {
+            JxlDecoderStatus::BasicInfo
+            | JxlDecoderStatus::ColorEncoding
+            | JxlDecoderStatus::PreviewImage
+            | JxlDecoderStatus::Frame
+            | JxlDecoderStatus::FullImage
+            | JxlDecoderStatus::JpegReconstruction
+            | JxlDecoderStatus::Box
+            | JxlDecoderStatus::FrameProgression => Self(status.0),
+            #[cfg(not(jxl_0_8))]
+            JxlDecoderStatus::Extensions | JxlDecoderStatus::DcImage => Self(status.0),
+            _ => panic!("the decoder status is not an event"),
+        }
+    }
+
+    #[must_use]
+    pub const fn bits(self) -> c_int {
+        self.0
+    }
+
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    #[must_use]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl BitOr for JxlDecoderEvents {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+impl BitOrAssign for JxlDecoderEvents {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = self.union(rhs);
+    }
+}
+
+impl From<JxlDecoderEvents> for c_int {
+    fn from(events: JxlDecoderEvents) -> Self {
+        events.bits()
+    }
 }
 
+/// Build [`JxlDecoderEvents`] from event statuses
+///
+/// Statuses named as `JxlDecoderStatus::Variant` are checked at compile time:
+///
+/// ```compile_fail
+/// # use jpegxl_sys::{jxl_dec_events, decoder::JxlDecoderStatus};
+/// let events = jxl_dec_events!(JxlDecoderStatus::BasicInfo, JxlDecoderStatus::NeedMoreInput);
+/// ```
+///
+/// Other expressions, such as statuses only known at runtime, are checked when evaluated, which
+/// panics if one is not an event:
+///
+/// ```
+/// # use jpegxl_sys::{jxl_dec_events, decoder::{JxlDecoderEvents, JxlDecoderStatus}};
+/// let status = JxlDecoderStatus::Frame;
+/// assert_eq!(jxl_dec_events!(status), JxlDecoderEvents::FRAME);
+/// ```
 #[macro_export]
 macro_rules! jxl_dec_events {
+    ( $( $ty: ident :: $variant: ident ),* ) => {
+        {
+            let tmp = $crate::decoder::JxlDecoderEvents::empty();
+            $(
+                let tmp = tmp.union({
+                    const EVENT: $crate::decoder::JxlDecoderEvents =
+                        $crate::decoder::JxlDecoderEvents::from_status($ty::$variant);
+                    EVENT
+                });
+            )*
+            tmp
+        }
+    };
     ( $( $x: expr ),* ) => {
         {
-            let mut tmp = 0;
+            let tmp = $crate::decoder::JxlDecoderEvents::empty();
             $(
-                tmp |= $x as i32;
+                let tmp = tmp.union($crate::decoder::JxlDecoderEvents::from_status($x));
             )*
             tmp
         }
@@ -73,180 +255,264 @@ pub type JxlImageOutCallback = extern "C" fn(
     pixels: *const c_void,
 );
 
-extern "C" {
-    pub fn JxlSignatureCheck(buf: *const u8, len: usize) -> JxlSignature;
-    pub fn JxlDecoderCreate(memory_manager: *const JxlMemoryManager) -> *mut JxlDecoder;
-    pub fn JxlDecoderReset(dec: *mut JxlDecoder);
-    pub fn JxlDecoderDestroy(dec: *mut JxlDecoder);
-    pub fn JxlDecoderVersion() -> u32;
-    pub fn JxlDecoderRewind(dec: *mut JxlDecoder);
-    pub fn JxlDecoderSkipFrames(dec: *mut JxlDecoder, amount: usize);
-
-    pub fn JxlDecoderDefaultPixelFormat(
-        dec: *const JxlDecoder,
-        format: *mut JxlPixelFormat,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderSetParallelRunner(
-        dec: *mut JxlDecoder,
-        parallel_runner: JxlParallelRunner,
-        parallel_runner_opaque: *mut c_void,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderSizeHintBasicInfo(dec: *const JxlDecoder) -> usize;
-
-    pub fn JxlDecoderSubscribeEvents(
-        dec: *mut JxlDecoder,
-        events_wanted: c_int,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderSetKeepOrientation(
-        dec: *mut JxlDecoder,
-        keep_orientation: bool,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderProcessInput(dec: *mut JxlDecoder) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderSetInput(
-        dec: *mut JxlDecoder,
-        data: *const u8,
-        size: usize,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderReleaseInput(dec: *mut JxlDecoder) -> usize;
-
-    pub fn JxlDecoderGetBasicInfo(
-        dec: *const JxlDecoder,
-        info: *mut JxlBasicInfo,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderGetExtraChannelInfo(
-        dec: *const JxlDecoder,
-        index: usize,
-        info: *mut JxlExtraChannelInfo,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderGetExtraChannelName(
-        dec: *const JxlDecoder,
-        index: usize,
-        name: *mut c_char,
-        size: usize,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderGetColorAsEncodedProfile(
-        dec: *const JxlDecoder,
-        format: *const JxlPixelFormat,
-        target: JxlColorProfileTarget,
-        color_encoding: *mut JxlColorEncoding,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderGetICCProfileSize(
-        dec: *const JxlDecoder,
-        format: *const JxlPixelFormat,
-        target: JxlColorProfileTarget,
-        size: *mut usize,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderGetColorAsICCProfile(
-        dec: *const JxlDecoder,
-        format: *const JxlPixelFormat,
-        target: JxlColorProfileTarget,
-        icc_profile: *mut u8,
-        size: usize,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderSetPreferredColorProfile(
-        dec: *mut JxlDecoder,
-        color_encoding: *const JxlColorEncoding,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderPreviewOutBufferSize(
-        dec: *const JxlDecoder,
-        format: *const JxlPixelFormat,
-        size: *mut usize,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderSetPreviewOutBuffer(
-        dec: *mut JxlDecoder,
-        format: *const JxlPixelFormat,
-        buffer: *mut c_void,
-        size: usize,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderGetFrameHeader(
-        dec: *const JxlDecoder,
-        header: *mut JxlFrameHeader,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderGetFrameName(
-        dec: *const JxlDecoder,
-        name: *mut c_char,
-        size: usize,
-    ) -> JxlDecoderStatus;
-
-    #[deprecated(
-        since = "0.5.0",
-        note = "please use `JxlDecoderSetImageOutCallback` instead"
-    )]
-    pub fn JxlDecoderDCOutBufferSize(
-        dec: *const JxlDecoder,
-        format: *const JxlPixelFormat,
-        size: *mut usize,
-    ) -> JxlDecoderStatus;
-
-    #[deprecated(
-        since = "0.5.0",
-        note = "please use `JxlDecoderSetImageOutCallback` instead"
-    )]
-    pub fn JxlDecoderSetDCOutBuffer(
-        dec: *mut JxlDecoder,
-        format: *const JxlPixelFormat,
-        buffer: *mut c_void,
-        size: usize,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderImageOutBufferSize(
-        dec: *const JxlDecoder,
-        format: *const JxlPixelFormat,
-        size: *mut usize,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderSetImageOutBuffer(
-        dec: *mut JxlDecoder,
-        format: *const JxlPixelFormat,
-        buffer: *mut c_void,
-        size: usize,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderSetImageOutCallback(
-        dec: *mut JxlDecoder,
-        format: *const JxlPixelFormat,
-        callback: JxlImageOutCallback,
-        opaque: *mut c_void,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderExtraChannelBufferSize(
-        dec: *const JxlDecoder,
-        format: *const JxlPixelFormat,
-        size: *mut usize,
-        index: u32,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderSetExtraChannelBuffer(
-        dec: *mut JxlDecoder,
-        format: *const JxlPixelFormat,
-        buffer: *mut c_void,
-        size: usize,
-        index: u32,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderSetJPEGBuffer(
-        dec: *mut JxlDecoder,
-        data: *mut u8,
-        size: usize,
-    ) -> JxlDecoderStatus;
-
-    pub fn JxlDecoderReleaseJPEGBuffer(dec: *mut JxlDecoder) -> usize;
-
-    pub fn JxlDecoderFlushImage(dec: *mut JxlDecoder) -> JxlDecoderStatus;
+pub type JxlImageOutInitCallback = extern "C" fn(
+    init_opaque: *mut c_void,
+    num_threads: usize,
+    num_pixels_per_thread: usize,
+) -> *mut c_void;
+
+pub type JxlImageOutRunCallback = extern "C" fn(
+    run_opaque: *mut c_void,
+    thread_id: usize,
+    x: usize,
+    y: usize,
+    num_pixels: usize,
+    pixels: *const c_void,
+);
+
+pub type JxlImageOutDestroyCallback = extern "C" fn(run_opaque: *mut c_void);
+
+#[cfg(feature = "bindgen")]
+include!(concat!(env!("OUT_DIR"), "/bindings/decoder.rs"));
+
+extern_functions! {
+    #[cfg(not(feature = "bindgen"))]
+    mod functions;
+
+    extern "C" {
+        pub fn JxlSignatureCheck(buf: *const u8, len: usize) -> JxlSignature;
+        pub fn JxlDecoderCreate(memory_manager: *const JxlMemoryManager) -> *mut JxlDecoder;
+        pub fn JxlDecoderReset(dec: *mut JxlDecoder);
+        pub fn JxlDecoderDestroy(dec: *mut JxlDecoder);
+        pub fn JxlDecoderVersion() -> u32;
+        pub fn JxlDecoderRewind(dec: *mut JxlDecoder);
+        pub fn JxlDecoderSkipFrames(dec: *mut JxlDecoder, amount: usize);
+
+        #[cfg(not(jxl_0_8))]
+        pub fn JxlDecoderDefaultPixelFormat(
+            dec: *const JxlDecoder,
+            format: *mut JxlPixelFormat,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderSetParallelRunner(
+            dec: *mut JxlDecoder,
+            parallel_runner: JxlParallelRunner,
+            parallel_runner_opaque: *mut c_void,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderSizeHintBasicInfo(dec: *const JxlDecoder) -> usize;
+
+        pub fn JxlDecoderSetKeepOrientation(
+            dec: *mut JxlDecoder,
+            keep_orientation: bool,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderSetProgressiveDetail(
+            dec: *mut JxlDecoder,
+            detail: JxlProgressiveDetail,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderGetIntendedDownsamplingRatio(dec: *mut JxlDecoder) -> usize;
+
+        pub fn JxlDecoderSetCoalescing(dec: *mut JxlDecoder, coalescing: bool) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderProcessInput(dec: *mut JxlDecoder) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderSetInput(
+            dec: *mut JxlDecoder,
+            data: *const u8,
+            size: usize,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderReleaseInput(dec: *mut JxlDecoder) -> usize;
+
+        pub fn JxlDecoderGetBasicInfo(
+            dec: *const JxlDecoder,
+            info: *mut JxlBasicInfo,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderGetExtraChannelInfo(
+            dec: *const JxlDecoder,
+            index: usize,
+            info: *mut JxlExtraChannelInfo,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderGetExtraChannelBlendInfo(
+            dec: *const JxlDecoder,
+            index: usize,
+            blend_info: *mut JxlBlendInfo,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderGetExtraChannelName(
+            dec: *const JxlDecoder,
+            index: usize,
+            name: *mut c_char,
+            size: usize,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderGetColorAsEncodedProfile(
+            dec: *const JxlDecoder,
+            format: *const JxlPixelFormat,
+            target: JxlColorProfileTarget,
+            color_encoding: *mut JxlColorEncoding,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderGetICCProfileSize(
+            dec: *const JxlDecoder,
+            format: *const JxlPixelFormat,
+            target: JxlColorProfileTarget,
+            size: *mut usize,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderGetColorAsICCProfile(
+            dec: *const JxlDecoder,
+            format: *const JxlPixelFormat,
+            target: JxlColorProfileTarget,
+            icc_profile: *mut u8,
+            size: usize,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderSetPreferredColorProfile(
+            dec: *mut JxlDecoder,
+            color_encoding: *const JxlColorEncoding,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderPreviewOutBufferSize(
+            dec: *const JxlDecoder,
+            format: *const JxlPixelFormat,
+            size: *mut usize,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderSetPreviewOutBuffer(
+            dec: *mut JxlDecoder,
+            format: *const JxlPixelFormat,
+            buffer: *mut c_void,
+            size: usize,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderGetFrameHeader(
+            dec: *const JxlDecoder,
+            header: *mut JxlFrameHeader,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderGetFrameName(
+            dec: *const JxlDecoder,
+            name: *mut c_char,
+            size: usize,
+        ) -> JxlDecoderStatus;
+
+        #[cfg(not(jxl_0_8))]
+        #[deprecated(
+            since = "0.5.0",
+            note = "please use `JxlDecoderSetImageOutCallback` instead"
+        )]
+        pub fn JxlDecoderDCOutBufferSize(
+            dec: *const JxlDecoder,
+            format: *const JxlPixelFormat,
+            size: *mut usize,
+        ) -> JxlDecoderStatus;
+
+        #[cfg(not(jxl_0_8))]
+        #[deprecated(
+            since = "0.5.0",
+            note = "please use `JxlDecoderSetImageOutCallback` instead"
+        )]
+        pub fn JxlDecoderSetDCOutBuffer(
+            dec: *mut JxlDecoder,
+            format: *const JxlPixelFormat,
+            buffer: *mut c_void,
+            size: usize,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderImageOutBufferSize(
+            dec: *const JxlDecoder,
+            format: *const JxlPixelFormat,
+            size: *mut usize,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderSetImageOutBuffer(
+            dec: *mut JxlDecoder,
+            format: *const JxlPixelFormat,
+            buffer: *mut c_void,
+            size: usize,
+        ) -> JxlDecoderStatus;
+
+        #[cfg(jxl_0_8)]
+        pub fn JxlDecoderSetImageOutBitDepth(
+            dec: *mut JxlDecoder,
+            bit_depth: *const JxlBitDepth,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderSetImageOutCallback(
+            dec: *mut JxlDecoder,
+            format: *const JxlPixelFormat,
+            callback: JxlImageOutCallback,
+            opaque: *mut c_void,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderSetMultithreadedImageOutCallback(
+            dec: *mut JxlDecoder,
+            format: *const JxlPixelFormat,
+            init_callback: JxlImageOutInitCallback,
+            run_callback: JxlImageOutRunCallback,
+            destroy_callback: JxlImageOutDestroyCallback,
+            init_opaque: *mut c_void,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderExtraChannelBufferSize(
+            dec: *const JxlDecoder,
+            format: *const JxlPixelFormat,
+            size: *mut usize,
+            index: u32,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderSetExtraChannelBuffer(
+            dec: *mut JxlDecoder,
+            format: *const JxlPixelFormat,
+            buffer: *mut c_void,
+            size: usize,
+            index: u32,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderSetJPEGBuffer(
+            dec: *mut JxlDecoder,
+            data: *mut u8,
+            size: usize,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderReleaseJPEGBuffer(dec: *mut JxlDecoder) -> usize;
+
+        pub fn JxlDecoderFlushImage(dec: *mut JxlDecoder) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderSetBoxBuffer(
+            dec: *mut JxlDecoder,
+            data: *mut u8,
+            size: usize,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderReleaseBoxBuffer(dec: *mut JxlDecoder) -> usize;
+
+        pub fn JxlDecoderSetDecompressBoxes(dec: *mut JxlDecoder, decompress: bool)
+            -> JxlDecoderStatus;
+
+        pub fn JxlDecoderGetBoxSizeRaw(dec: *const JxlDecoder, size: *mut u64) -> JxlDecoderStatus;
+    }
+}
+
+// Declared by hand with more precise types than the header, even with the `bindgen` feature
+extern_functions! {
+    mod precise_functions;
+
+    extern "C" {
+        pub fn JxlDecoderSubscribeEvents(
+            dec: *mut JxlDecoder,
+            events_wanted: JxlDecoderEvents,
+        ) -> JxlDecoderStatus;
+
+        pub fn JxlDecoderGetBoxType(
+            dec: *mut JxlDecoder,
+            box_type: *mut JxlBoxType,
+            decompressed: bool,
+        ) -> JxlDecoderStatus;
+    }
 }
@@ -15,12 +15,22 @@ You should have received a copy of the GNU General Public License
 along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use std::{ffi::c_void, os::raw::c_int};
+// The generated functions name the C types by their full path
+#![cfg_attr(feature = "bindgen", allow(unused_imports))]
+
+use std::{ffi::c_void, mem::MaybeUninit, os::raw::c_char};
+
+#[cfg(not(jxl_0_8))]
+use std::os::raw::c_int;
 
 use crate::common::{
-    JxlBasicInfo, JxlColorEncoding, JxlMemoryManager, JxlParallelRunner, JxlPixelFormat,
+    JxlBasicInfo, JxlBlendInfo, JxlBoxType, JxlColorEncoding, JxlExtraChannelInfo,
+    JxlExtraChannelType, JxlFrameHeader, JxlMemoryManager, JxlParallelRunner, JxlPixelFormat,
 };
 
+#[cfg(jxl_0_8)]
+use crate::common::JxlBitDepth;
+
 // Opaque type
 #[allow(clippy::module_name_repetitions)]
 #[repr(C)]
@@ -29,107 +39,409 @@ pub struct JxlEncoder {
 }
 
 // Opaque type
+#[allow(clippy::module_name_repetitions)]
 #[repr(C)]
-pub struct JxlEncoderOptions {
+pub struct JxlEncoderFrameSettings {
     _unused: [u8; 0],
 }
 
+#[cfg(not(jxl_0_8))]
+#[deprecated(since = "0.7.0", note = "please use `JxlEncoderFrameSettings` instead")]
+pub type JxlEncoderOptions = JxlEncoderFrameSettings;
+
+ffi_enum! {
+    pub struct JxlEncoderStatus => pub enum EncoderStatus {
+        Success = 0,
+        Error = 1,
+        NeedMoreOutput = 2,
+        NotSupported = 3,
+    }
+}
+
+/// Id of per-frame options to set to [`JxlEncoderFrameSettings`] with
+/// [`JxlEncoderFrameSettingsSetOption`] or [`JxlEncoderFrameSettingsSetFloatOption`].
+///
+/// Complete as of libjxl v0.8.0, the last ones only with it.
+#[allow(clippy::module_name_repetitions)]
 #[repr(C)]
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub enum JxlEncoderStatus {
-    Success = 0,
-    Error = 1,
-    NeedMoreOutput = 2,
-    NotSupported = 3,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JxlEncoderFrameSettingId {
+    /// Encoder effort, from 1 (fastest) to 9 (slowest). Default: 7.
+    Effort = 0,
+    /// Decoding speed tier, from 0 (slowest decoding, best quality) to 4. Default: 0.
+    DecodingSpeed = 1,
+    /// Downsampling factor of the frame: -1 (default), 1, 2, 4 or 8.
+    Resampling = 2,
+    /// Downsampling factor of extra channels: -1 (default), 1, 2, 4 or 8.
+    ExtraChannelResampling = 3,
+    /// Whether the input is already downsampled by [`Self::Resampling`]: 0 (default) or 1.
+    AlreadyDownsampled = 4,
+    /// Photon noise amount as ISO equivalent, set as float. Default: 0 (disabled).
+    PhotonNoise = 5,
+    /// Adaptive noise generation: -1 (default), 0 (off) or 1 (on).
+    Noise = 6,
+    /// Dots generation: -1 (default), 0 (off) or 1 (on).
+    Dots = 7,
+    /// Patches generation: -1 (default), 0 (off) or 1 (on).
+    Patches = 8,
+    /// Edge preserving filter level: -1 (default) or 0 to 3.
+    Epf = 9,
+    /// Gaborish filter: -1 (default), 0 (off) or 1 (on).
+    Gaborish = 10,
+    /// Modular mode: -1 (default), 0 (`VarDCT`) or 1 (modular).
+    Modular = 11,
+    /// Preserve color of invisible pixels: -1 (default), 0 (off) or 1 (on).
+    KeepInvisible = 12,
+    /// Group order: -1 (default), 0 (scanline) or 1 (center-first).
+    GroupOrder = 13,
+    /// X coordinate of the center for center-first group order, -1 for the image center.
+    GroupOrderCenterX = 14,
+    /// Y coordinate of the center for center-first group order, -1 for the image center.
+    GroupOrderCenterY = 15,
+    /// Modular progressive (squeeze) mode: -1 (default), 0 (off) or 1 (on).
+    Responsive = 16,
+    /// `VarDCT` spectral progression of AC coefficients: -1 (default), 0 (off) or 1 (on).
+    ProgressiveAc = 17,
+    /// `VarDCT` quantization progression of AC coefficients: -1 (default), 0 (off) or 1 (on).
+    QprogressiveAc = 18,
+    /// Progressive DC: -1 (default), 0 (off), 1 or 2 (number of extra DC levels).
+    ProgressiveDc = 19,
+    /// Percentage of pixels for global channel palette, set as float. -1 for default.
+    ChannelColorsGlobalPercent = 20,
+    /// Percentage of pixels for per-group channel palette, set as float. -1 for default.
+    ChannelColorsGroupPercent = 21,
+    /// Maximum number of colors for palette: -1 (default) or 0 to disable.
+    PaletteColors = 22,
+    /// Delta palette: -1 (default), 0 (off) or 1 (on, lossy).
+    LossyPalette = 23,
+    /// Color transform for modular: -1 (default), 0 (XYB), 1 (none) or 2 (YCbCr).
+    ColorTransform = 24,
+    /// Reversible color transform for modular: -1 (default) or 0 to 41.
+    ModularColorSpace = 25,
+    /// Group size for modular: -1 (default), 0 (128), 1 (256), 2 (512) or 3 (1024).
+    ModularGroupSize = 26,
+    /// Predictor for modular: -1 (default) or 0 to 15.
+    ModularPredictor = 27,
+    /// Fraction of pixels used to learn MA trees, set as float. -1 for default.
+    ModularMaTreeLearningPercent = 28,
+    /// Number of extra previous channels used for MA trees: -1 (default) or 0 to 11.
+    ModularNbPrevChannels = 29,
+    /// Chroma from luma for JPEG reconstruction: -1 (default), 0 (off) or 1 (on).
+    JpegReconCfl = 30,
+    /// Frame index box: 0 (default) or 1 to mark the frame as indexed.
+    IndexBox = 31,
+    /// Brotli effort for compressed boxes: -1 (default) or 0 to 11.
+    BrotliEffort = 32,
+    /// Brotli compression of the metadata boxes of a JPEG frame: -1 (default), 0 (off) or 1 (on).
+    #[cfg(jxl_0_8)]
+    JpegCompressBoxes = 33,
+    /// Buffering of chunked frames: -1 (default), or 0 (everything) to 3 (minimal).
+    #[cfg(jxl_0_8)]
+    Buffering = 34,
+    /// Keep the Exif box of a JPEG frame: -1 (default), 0 (discard) or 1 (keep).
+    #[cfg(jxl_0_8)]
+    JpegKeepExif = 35,
+    /// Keep the XMP box of a JPEG frame: -1 (default), 0 (discard) or 1 (keep).
+    #[cfg(jxl_0_8)]
+    JpegKeepXmp = 36,
+    /// Keep the JUMBF box of a JPEG frame: -1 (default), 0 (discard) or 1 (keep).
+    #[cfg(jxl_0_8)]
+    JpegKeepJumbf = 37,
 }
 
-extern "C" {
-    pub fn JxlEncoderVersion() -> u32;
+#[cfg(feature = "bindgen")]
+include!(concat!(env!("OUT_DIR"), "/bindings/encoder.rs"));
+
+extern_functions! {
+    #[cfg(not(feature = "bindgen"))]
+    mod functions;
+
+    extern "C" {
+        pub fn JxlEncoderVersion() -> u32;
+
+        pub fn JxlEncoderCreate(memory_manager: *const JxlMemoryManager) -> *mut JxlEncoder;
+
+        pub fn JxlEncoderReset(enc: *mut JxlEncoder);
+
+        pub fn JxlEncoderDestroy(enc: *mut JxlEncoder);
+
+        pub fn JxlEncoderSetParallelRunner(
+            enc: *mut JxlEncoder,
+            parallel_runner: JxlParallelRunner,
+            parallel_runner_opaque: *mut c_void,
+        ) -> JxlEncoderStatus;
+
+        pub fn JxlEncoderProcessOutput(
+            enc: *mut JxlEncoder,
+            next_out: *mut *mut u8,
+            avail_out: *mut usize,
+        ) -> JxlEncoderStatus;
+
+        pub fn JxlEncoderAddJPEGFrame(
+            frame_settings: *const JxlEncoderFrameSettings,
+            buffer: *const u8,
+            size: usize,
+        ) -> JxlEncoderStatus;
+
+        pub fn JxlEncoderAddImageFrame(
+            frame_settings: *const JxlEncoderFrameSettings,
+            pixel_format: *const JxlPixelFormat,
+            buffer: *const c_void,
+            size: usize,
+        ) -> JxlEncoderStatus;
+
+        pub fn JxlEncoderSetExtraChannelBuffer(
+            frame_settings: *const JxlEncoderFrameSettings,
+            pixel_format: *const JxlPixelFormat,
+            buffer: *const c_void,
+            size: usize,
+            index: u32,
+        ) -> JxlEncoderStatus;
+
+        pub fn JxlEncoderUseBoxes(enc: *mut JxlEncoder) -> JxlEncoderStatus;
+
+        pub fn JxlEncoderCloseBoxes(enc: *mut JxlEncoder);
+
+        pub fn JxlEncoderCloseFrames(enc: *mut JxlEncoder);
 
-    pub fn JxlEncoderCreate(memory_manager: *const JxlMemoryManager) -> *mut JxlEncoder;
+        pub fn JxlEncoderCloseInput(enc: *mut JxlEncoder);
 
-    pub fn JxlEncoderReset(enc: *mut JxlEncoder);
+        pub fn JxlEncoderSetColorEncoding(
+            enc: *mut JxlEncoder,
+            color: *const JxlColorEncoding,
+        ) -> JxlEncoderStatus;
 
-    pub fn JxlEncoderDestroy(enc: *mut JxlEncoder);
+        pub fn JxlEncoderSetICCProfile(
+            enc: *mut JxlEncoder,
+            icc_profile: *const u8,
+            size: usize,
+        ) -> JxlEncoderStatus;
 
-    pub fn JxlEncoderSetParallelRunner(
-        enc: *mut JxlEncoder,
-        parallel_runner: JxlParallelRunner,
-        parallel_runner_opaque: *mut c_void,
-    ) -> JxlEncoderStatus;
+        pub fn JxlEncoderInitBasicInfo(info: *mut JxlBasicInfo);
 
-    pub fn JxlEncoderProcessOutput(
-        enc: *mut JxlEncoder,
-        next_out: *mut *mut u8,
-        avail_out: *mut usize,
-    ) -> JxlEncoderStatus;
+        pub fn JxlEncoderSetBasicInfo(
+            enc: *mut JxlEncoder,
+            info: *const JxlBasicInfo,
+        ) -> JxlEncoderStatus;
 
-    pub fn JxlEncoderAddJPEGFrame(
-        options: *const JxlEncoderOptions,
-        buffer: *const u8,
-        size: usize,
-    ) -> JxlEncoderStatus;
+        pub fn JxlEncoderInitExtraChannelInfo(
+            type_: JxlExtraChannelType,
+            info: *mut JxlExtraChannelInfo,
+        );
 
-    pub fn JxlEncoderAddImageFrame(
-        options: *const JxlEncoderOptions,
-        pixel_format: *const JxlPixelFormat,
-        buffer: *const c_void,
-        size: usize,
-    ) -> JxlEncoderStatus;
+        pub fn JxlEncoderSetExtraChannelInfo(
+            enc: *mut JxlEncoder,
+            index: usize,
+            info: *const JxlExtraChannelInfo,
+        ) -> JxlEncoderStatus;
 
-    pub fn JxlEncoderCloseInput(enc: *mut JxlEncoder);
+        pub fn JxlEncoderSetExtraChannelName(
+            enc: *mut JxlEncoder,
+            index: usize,
+            name: *const c_char,
+            size: usize,
+        ) -> JxlEncoderStatus;
 
-    pub fn JxlEncoderSetColorEncoding(
-        enc: *mut JxlEncoder,
-        color: *const JxlColorEncoding,
-    ) -> JxlEncoderStatus;
+        pub fn JxlEncoderInitFrameHeader(frame_header: *mut JxlFrameHeader);
 
-    pub fn JxlEncoderSetICCProfile(
-        enc: *mut JxlEncoder,
-        icc_profile: *const u8,
-        size: usize,
-    ) -> JxlEncoderStatus;
+        pub fn JxlEncoderInitBlendInfo(blend_info: *mut JxlBlendInfo);
 
-    pub fn JxlEncoderInitBasicInfo(info: *mut JxlBasicInfo);
+        pub fn JxlEncoderSetFrameHeader(
+            frame_settings: *mut JxlEncoderFrameSettings,
+            frame_header: *const JxlFrameHeader,
+        ) -> JxlEncoderStatus;
 
-    pub fn JxlEncoderSetBasicInfo(
-        enc: *mut JxlEncoder,
-        info: *const JxlBasicInfo,
-    ) -> JxlEncoderStatus;
+        pub fn JxlEncoderSetExtraChannelBlendInfo(
+            frame_settings: *mut JxlEncoderFrameSettings,
+            index: usize,
+            blend_info: *const JxlBlendInfo,
+        ) -> JxlEncoderStatus;
 
-    pub fn JxlEncoderStoreJPEGMetadata(
-        enc: *mut JxlEncoder,
-        store_jpeg_metadata: bool,
-    ) -> JxlEncoderStatus;
+        pub fn JxlEncoderSetFrameName(
+            frame_settings: *mut JxlEncoderFrameSettings,
+            frame_name: *const c_char,
+        ) -> JxlEncoderStatus;
 
-    pub fn JxlEncoderUseContainer(enc: *mut JxlEncoder, use_container: bool) -> JxlEncoderStatus;
+        pub fn JxlEncoderStoreJPEGMetadata(
+            enc: *mut JxlEncoder,
+            store_jpeg_metadata: bool,
+        ) -> JxlEncoderStatus;
 
-    pub fn JxlEncoderOptionsSetLossless(
-        options: *mut JxlEncoderOptions,
-        lossless: bool,
-    ) -> JxlEncoderStatus;
+        pub fn JxlEncoderUseContainer(enc: *mut JxlEncoder, use_container: bool) -> JxlEncoderStatus;
 
-    pub fn JxlEncoderOptionsSetDecodingSpeed(
-        options: *mut JxlEncoderOptions,
-        tier: i32,
-    ) -> JxlEncoderStatus;
+        pub fn JxlEncoderFrameSettingsCreate(
+            enc: *mut JxlEncoder,
+            source: *const JxlEncoderFrameSettings,
+        ) -> *mut JxlEncoderFrameSettings;
 
-    pub fn JxlEncoderOptionsSetEffort(
-        options: *mut JxlEncoderOptions,
-        effort: c_int,
-    ) -> JxlEncoderStatus;
+        pub fn JxlEncoderFrameSettingsSetOption(
+            frame_settings: *mut JxlEncoderFrameSettings,
+            option: JxlEncoderFrameSettingId,
+            value: i64,
+        ) -> JxlEncoderStatus;
 
-    pub fn JxlEncoderOptionsSetDistance(
-        options: *mut JxlEncoderOptions,
-        distance: f32,
-    ) -> JxlEncoderStatus;
+        pub fn JxlEncoderFrameSettingsSetFloatOption(
+            frame_settings: *mut JxlEncoderFrameSettings,
+            option: JxlEncoderFrameSettingId,
+            value: f32,
+        ) -> JxlEncoderStatus;
 
-    pub fn JxlEncoderOptionsCreate(
-        enc: *mut JxlEncoder,
-        source: *const JxlEncoderOptions,
-    ) -> *mut JxlEncoderOptions;
+        pub fn JxlEncoderSetFrameLossless(
+            frame_settings: *mut JxlEncoderFrameSettings,
+            lossless: bool,
+        ) -> JxlEncoderStatus;
+
+        #[cfg(jxl_0_8)]
+        pub fn JxlEncoderSetFrameBitDepth(
+            frame_settings: *mut JxlEncoderFrameSettings,
+            bit_depth: *const JxlBitDepth,
+        ) -> JxlEncoderStatus;
+
+        pub fn JxlEncoderSetFrameDistance(
+            frame_settings: *mut JxlEncoderFrameSettings,
+            distance: f32,
+        ) -> JxlEncoderStatus;
+
+        #[cfg(not(jxl_0_8))]
+        #[deprecated(
+            since = "0.7.0",
+            note = "please use `JxlEncoderSetFrameLossless` instead"
+        )]
+        pub fn JxlEncoderOptionsSetLossless(
+            options: *mut JxlEncoderFrameSettings,
+            lossless: bool,
+        ) -> JxlEncoderStatus;
+
+        #[cfg(not(jxl_0_8))]
+        #[deprecated(
+            since = "0.7.0",
+            note = "please use `JxlEncoderFrameSettingsSetOption` with `DecodingSpeed` instead"
+        )]
+        pub fn JxlEncoderOptionsSetDecodingSpeed(
+            options: *mut JxlEncoderFrameSettings,
+            tier: i32,
+        ) -> JxlEncoderStatus;
+
+        #[cfg(not(jxl_0_8))]
+        #[deprecated(
+            since = "0.7.0",
+            note = "please use `JxlEncoderFrameSettingsSetOption` with `Effort` instead"
+        )]
+        pub fn JxlEncoderOptionsSetEffort(
+            options: *mut JxlEncoderFrameSettings,
+            effort: c_int,
+        ) -> JxlEncoderStatus;
+
+        #[cfg(not(jxl_0_8))]
+        #[deprecated(
+            since = "0.7.0",
+            note = "please use `JxlEncoderSetFrameDistance` instead"
+        )]
+        pub fn JxlEncoderOptionsSetDistance(
+            options: *mut JxlEncoderFrameSettings,
+            distance: f32,
+        ) -> JxlEncoderStatus;
+
+        #[cfg(not(jxl_0_8))]
+        #[deprecated(
+            since = "0.7.0",
+            note = "please use `JxlEncoderFrameSettingsCreate` instead"
+        )]
+        pub fn JxlEncoderOptionsCreate(
+            enc: *mut JxlEncoder,
+            source: *const JxlEncoderFrameSettings,
+        ) -> *mut JxlEncoderFrameSettings;
+
+        pub fn JxlColorEncodingSetToSRGB(color_encoding: *mut JxlColorEncoding, is_gray: bool);
+
+        pub fn JxlColorEncodingSetToLinearSRGB(color_encoding: *mut JxlColorEncoding, is_gray: bool);
+    }
+}
+
+// Declared by hand with more precise types than the header, even with the `bindgen` feature
+extern_functions! {
+    mod precise_functions;
+
+    extern "C" {
+        pub fn JxlEncoderAddBox(
+            enc: *mut JxlEncoder,
+            box_type: *const JxlBoxType,
+            contents: *const u8,
+            size: usize,
+            compress_box: bool,
+        ) -> JxlEncoderStatus;
+    }
+}
+
+impl JxlBasicInfo {
+    /// Basic info with the defaults of `JxlEncoderInitBasicInfo`, to fill before
+    /// `JxlEncoderSetBasicInfo`
+    #[must_use]
+    pub fn default_for_encoder() -> Self {
+        // Zeroed, as libjxl sets the fields one by one and leaves the padding alone
+        let mut info = MaybeUninit::zeroed();
+        unsafe {
+            JxlEncoderInitBasicInfo(info.as_mut_ptr());
+            info.assume_init()
+        }
+    }
+}
+
+impl JxlExtraChannelInfo {
+    /// Extra channel info of type `type_` with the defaults of `JxlEncoderInitExtraChannelInfo`
+    #[must_use]
+    pub fn default_for_encoder(type_: JxlExtraChannelType) -> Self {
+        let mut info = MaybeUninit::zeroed();
+        unsafe {
+            JxlEncoderInitExtraChannelInfo(type_, info.as_mut_ptr());
+            info.assume_init()
+        }
+    }
+}
+
+impl JxlFrameHeader {
+    /// Frame header with the defaults of `JxlEncoderInitFrameHeader`
+    #[must_use]
+    pub fn default_for_encoder() -> Self {
+        let mut header = MaybeUninit::zeroed();
+        unsafe {
+            JxlEncoderInitFrameHeader(header.as_mut_ptr());
+            header.assume_init()
+        }
+    }
+}
+
+impl JxlBlendInfo {
+    /// Blend info with the defaults of `JxlEncoderInitBlendInfo`
+    #[must_use]
+    pub fn default_for_encoder() -> Self {
+        let mut info = MaybeUninit::zeroed();
+        unsafe {
+            JxlEncoderInitBlendInfo(info.as_mut_ptr());
+            info.assume_init()
+        }
+    }
+}
 
-    pub fn JxlColorEncodingSetToSRGB(color_encoding: *mut JxlColorEncoding, is_gray: bool);
+impl JxlColorEncoding {
+    /// sRGB color encoding, set by `JxlColorEncodingSetToSRGB`
+    #[must_use]
+    pub fn srgb(is_gray: bool) -> Self {
+        let mut encoding = MaybeUninit::zeroed();
+        unsafe {
+            JxlColorEncodingSetToSRGB(encoding.as_mut_ptr(), is_gray);
+            encoding.assume_init()
+        }
+    }
 
-    pub fn JxlColorEncodingSetToLinearSRGB(color_encoding: *mut JxlColorEncoding, is_gray: bool);
+    /// sRGB color encoding with a linear transfer function, set by
+    /// `JxlColorEncodingSetToLinearSRGB`
+    #[must_use]
+    pub fn linear_srgb(is_gray: bool) -> Self {
+        let mut encoding = MaybeUninit::zeroed();
+        unsafe {
+            JxlColorEncodingSetToLinearSRGB(encoding.as_mut_ptr(), is_gray);
+            encoding.assume_init()
+        }
+    }
 }
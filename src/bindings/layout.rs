@@ -0,0 +1,402 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Checks the hand-written bindings against declarations bindgen generates from the headers of
+//! the linked libjxl. Run with `cargo test --features layout-tests layout`.
+
+use std::{
+    mem::{align_of, offset_of, size_of},
+    os::raw::c_int,
+};
+
+use super::*;
+
+#[allow(
+    dead_code,
+    non_camel_case_types,
+    non_snake_case,
+    non_upper_case_globals,
+    clippy::pedantic
+)]
+mod c {
+    include!(concat!(env!("OUT_DIR"), "/layout_bindings.rs"));
+}
+
+macro_rules! assert_struct {
+    ($name:ident { $( $field:ident $(= $c_field:ident)? ),* $(,)? }) => {
+        assert_eq!(
+            size_of::<$name>(),
+            size_of::<c::$name>(),
+            concat!("Size of ", stringify!($name))
+        );
+        assert_eq!(
+            align_of::<$name>(),
+            align_of::<c::$name>(),
+            concat!("Alignment of ", stringify!($name))
+        );
+        $( assert_struct!(@offset $name, $field $(= $c_field)?); )*
+    };
+    (@offset $name:ident, $field:ident) => {
+        assert_struct!(@offset $name, $field = $field)
+    };
+    (@offset $name:ident, $field:ident = $c_field:ident) => {
+        assert_eq!(
+            offset_of!($name, $field),
+            offset_of!(c::$name, $c_field),
+            concat!("Offset of ", stringify!($name), "::", stringify!($field))
+        )
+    };
+}
+
+macro_rules! assert_enum {
+    (@value newtype $value:expr) => {
+        i64::from($value.0)
+    };
+    (@value enum $value:expr) => {
+        $value as i64
+    };
+    ($kind:ident $name:ident {
+        $( $(#[cfg($cfg:meta)])? $variant:ident = $c_value:ident ),* $(,)?
+    }) => {
+        assert_eq!(
+            size_of::<$name>(),
+            size_of::<c::$name>(),
+            concat!("Size of ", stringify!($name))
+        );
+        $(
+            $(#[cfg($cfg)])?
+            assert_eq!(
+                assert_enum!(@value $kind $name::$variant),
+                i64::from(c::$c_value),
+                concat!("Value of ", stringify!($name), "::", stringify!($variant))
+            );
+        )*
+    };
+}
+
+#[test]
+fn test_layout_version() {
+    let version = c::JPEGXL_MAJOR_VERSION * 1_000_000
+        + c::JPEGXL_MINOR_VERSION * 1000
+        + c::JPEGXL_PATCH_VERSION;
+    unsafe {
+        assert_eq!(JxlDecoderVersion(), version, "Headers of another libjxl");
+    }
+}
+
+#[test]
+fn test_layout_structs() {
+    assert_struct!(JxlPixelFormat {
+        num_channels,
+        data_type,
+        endianness,
+        align,
+    });
+    assert_struct!(JxlColorEncoding {
+        color_space,
+        white_point,
+        white_point_xy,
+        primaries,
+        primaries_red_xy,
+        primaries_green_xy,
+        primaries_blue_xy,
+        transfer_function,
+        gamma,
+        rendering_intent,
+    });
+    assert_struct!(JxlPreviewHeader { xsize, ysize });
+    assert_struct!(JxlAnimationHeader {
+        tps_numerator,
+        tps_denominator,
+        num_loops,
+        have_timecodes,
+    });
+    assert_struct!(JxlBasicInfo {
+        have_container,
+        xsize,
+        ysize,
+        bits_per_sample,
+        exponent_bits_per_sample,
+        intensity_target,
+        min_nits,
+        relative_to_max_display,
+        linear_below,
+        uses_original_profile,
+        have_preview,
+        have_animation,
+        orientation,
+        num_color_channels,
+        num_extra_channels,
+        alpha_bits,
+        alpha_exponent_bits,
+        alpha_premultiplied,
+        preview,
+        animation,
+        intrinsic_xsize,
+        intrinsic_ysize,
+    });
+    assert_struct!(JxlExtraChannelInfo {
+        type_,
+        bits_per_sample,
+        exponent_bits_per_sample,
+        dim_shift,
+        name_length,
+        alpha_associated = alpha_premultiplied,
+        spot_color,
+        cfa_channel,
+    });
+    assert_struct!(JxlHeaderExtensions { extensions });
+    assert_struct!(JxlBlendInfo {
+        blendmode,
+        source,
+        alpha,
+        clamp,
+    });
+    assert_struct!(JxlLayerInfo {
+        have_crop,
+        crop_x0,
+        crop_y0,
+        xsize,
+        ysize,
+        blend_info,
+        save_as_reference,
+    });
+    assert_struct!(JxlFrameHeader {
+        duration,
+        timecode,
+        name_length,
+        is_last,
+        layer_info,
+    });
+    assert_struct!(JxlMemoryManager {
+        opaque,
+        alloc,
+        free,
+    });
+
+    assert_eq!(size_of::<JxlBoxType>(), size_of::<c::JxlBoxType>());
+
+    #[cfg(jxl_0_8)]
+    assert_struct!(JxlBitDepth {
+        type_,
+        bits_per_sample,
+        exponent_bits_per_sample,
+    });
+}
+
+#[test]
+#[allow(clippy::too_many_lines)]
+fn test_layout_enums() {
+    assert_eq!(size_of::<JxlBool>(), size_of::<c_int>());
+    assert_eq!(i64::from(JxlBool::True.0), i64::from(c::JXL_TRUE));
+    assert_eq!(i64::from(JxlBool::False.0), i64::from(c::JXL_FALSE));
+
+    assert_eq!(
+        i64::from(JXL_PARALLEL_RET_RUNNER_ERROR),
+        i64::from(c::JXL_PARALLEL_RET_RUNNER_ERROR)
+    );
+
+    assert_enum!(newtype JxlDataType {
+        Float = JxlDataType_JXL_TYPE_FLOAT,
+        Boolean = JxlDataType_JXL_TYPE_BOOLEAN,
+        Uint8 = JxlDataType_JXL_TYPE_UINT8,
+        Uint16 = JxlDataType_JXL_TYPE_UINT16,
+        Uint32 = JxlDataType_JXL_TYPE_UINT32,
+        Float16 = JxlDataType_JXL_TYPE_FLOAT16,
+    });
+    assert_enum!(newtype JxlEndianness {
+        Native = JxlEndianness_JXL_NATIVE_ENDIAN,
+        Little = JxlEndianness_JXL_LITTLE_ENDIAN,
+        Big = JxlEndianness_JXL_BIG_ENDIAN,
+    });
+    assert_enum!(newtype JxlColorSpace {
+        Rgb = JxlColorSpace_JXL_COLOR_SPACE_RGB,
+        Gray = JxlColorSpace_JXL_COLOR_SPACE_GRAY,
+        Xyb = JxlColorSpace_JXL_COLOR_SPACE_XYB,
+        Unknown = JxlColorSpace_JXL_COLOR_SPACE_UNKNOWN,
+    });
+    assert_enum!(newtype JxlWhitePoint {
+        D65 = JxlWhitePoint_JXL_WHITE_POINT_D65,
+        Custom = JxlWhitePoint_JXL_WHITE_POINT_CUSTOM,
+        E = JxlWhitePoint_JXL_WHITE_POINT_E,
+        Dci = JxlWhitePoint_JXL_WHITE_POINT_DCI,
+    });
+    assert_enum!(newtype JxlPrimaries {
+        SRgb = JxlPrimaries_JXL_PRIMARIES_SRGB,
+        Custom = JxlPrimaries_JXL_PRIMARIES_CUSTOM,
+        Rec2100 = JxlPrimaries_JXL_PRIMARIES_2100,
+        P3 = JxlPrimaries_JXL_PRIMARIES_P3,
+    });
+    assert_enum!(newtype JxlTransferFunction {
+        Rec709 = JxlTransferFunction_JXL_TRANSFER_FUNCTION_709,
+        Unknown = JxlTransferFunction_JXL_TRANSFER_FUNCTION_UNKNOWN,
+        Linear = JxlTransferFunction_JXL_TRANSFER_FUNCTION_LINEAR,
+        SRgb = JxlTransferFunction_JXL_TRANSFER_FUNCTION_SRGB,
+        Pq = JxlTransferFunction_JXL_TRANSFER_FUNCTION_PQ,
+        Dci = JxlTransferFunction_JXL_TRANSFER_FUNCTION_DCI,
+        Hlg = JxlTransferFunction_JXL_TRANSFER_FUNCTION_HLG,
+        Gamma = JxlTransferFunction_JXL_TRANSFER_FUNCTION_GAMMA,
+    });
+    assert_enum!(newtype JxlRenderingIntent {
+        Perceptual = JxlRenderingIntent_JXL_RENDERING_INTENT_PERCEPTUAL,
+        Relative = JxlRenderingIntent_JXL_RENDERING_INTENT_RELATIVE,
+        Saturation = JxlRenderingIntent_JXL_RENDERING_INTENT_SATURATION,
+        Absolute = JxlRenderingIntent_JXL_RENDERING_INTENT_ABSOLUTE,
+    });
+    assert_enum!(newtype JxlOrientation {
+        Identity = JxlOrientation_JXL_ORIENT_IDENTITY,
+        FlipHorizontal = JxlOrientation_JXL_ORIENT_FLIP_HORIZONTAL,
+        Rotate180 = JxlOrientation_JXL_ORIENT_ROTATE_180,
+        FlipVertical = JxlOrientation_JXL_ORIENT_FLIP_VERTICAL,
+        Transpose = JxlOrientation_JXL_ORIENT_TRANSPOSE,
+        Rotate90Cw = JxlOrientation_JXL_ORIENT_ROTATE_90_CW,
+        AntiTranspose = JxlOrientation_JXL_ORIENT_ANTI_TRANSPOSE,
+        Rotate90Ccw = JxlOrientation_JXL_ORIENT_ROTATE_90_CCW,
+    });
+    assert_enum!(newtype JxlExtraChannelType {
+        Alpha = JxlExtraChannelType_JXL_CHANNEL_ALPHA,
+        Depth = JxlExtraChannelType_JXL_CHANNEL_DEPTH,
+        SpotColor = JxlExtraChannelType_JXL_CHANNEL_SPOT_COLOR,
+        SelectionMask = JxlExtraChannelType_JXL_CHANNEL_SELECTION_MASK,
+        Black = JxlExtraChannelType_JXL_CHANNEL_BLACK,
+        Cfa = JxlExtraChannelType_JXL_CHANNEL_CFA,
+        Thermal = JxlExtraChannelType_JXL_CHANNEL_THERMAL,
+        Reserved0 = JxlExtraChannelType_JXL_CHANNEL_RESERVED0,
+        Reserved1 = JxlExtraChannelType_JXL_CHANNEL_RESERVED1,
+        Reserved2 = JxlExtraChannelType_JXL_CHANNEL_RESERVED2,
+        Reserved3 = JxlExtraChannelType_JXL_CHANNEL_RESERVED3,
+        Reserved4 = JxlExtraChannelType_JXL_CHANNEL_RESERVED4,
+        Reserved5 = JxlExtraChannelType_JXL_CHANNEL_RESERVED5,
+        Reserved6 = JxlExtraChannelType_JXL_CHANNEL_RESERVED6,
+        Reserved7 = JxlExtraChannelType_JXL_CHANNEL_RESERVED7,
+        Unknown = JxlExtraChannelType_JXL_CHANNEL_UNKNOWN,
+        Optional = JxlExtraChannelType_JXL_CHANNEL_OPTIONAL,
+    });
+    assert_enum!(newtype JxlBlendMode {
+        Replace = JxlBlendMode_JXL_BLEND_REPLACE,
+        Add = JxlBlendMode_JXL_BLEND_ADD,
+        Blend = JxlBlendMode_JXL_BLEND_BLEND,
+        MulAdd = JxlBlendMode_JXL_BLEND_MULADD,
+        Mul = JxlBlendMode_JXL_BLEND_MUL,
+    });
+    assert_enum!(newtype JxlSignature {
+        NotEnoughBytes = JxlSignature_JXL_SIG_NOT_ENOUGH_BYTES,
+        Invalid = JxlSignature_JXL_SIG_INVALID,
+        Codestream = JxlSignature_JXL_SIG_CODESTREAM,
+        Container = JxlSignature_JXL_SIG_CONTAINER,
+    });
+    assert_enum!(
+        enum JxlColorProfileTarget {
+            Original = JxlColorProfileTarget_JXL_COLOR_PROFILE_TARGET_ORIGINAL,
+            Data = JxlColorProfileTarget_JXL_COLOR_PROFILE_TARGET_DATA,
+        }
+    );
+
+    assert_enum!(newtype JxlDecoderStatus {
+        Success = JxlDecoderStatus_JXL_DEC_SUCCESS,
+        Error = JxlDecoderStatus_JXL_DEC_ERROR,
+        NeedMoreInput = JxlDecoderStatus_JXL_DEC_NEED_MORE_INPUT,
+        NeedPreviewOutBuffer = JxlDecoderStatus_JXL_DEC_NEED_PREVIEW_OUT_BUFFER,
+        #[cfg(not(jxl_0_8))]
+        NeedDcOutBuffer = JxlDecoderStatus_JXL_DEC_NEED_DC_OUT_BUFFER,
+        NeedImageOutBuffer = JxlDecoderStatus_JXL_DEC_NEED_IMAGE_OUT_BUFFER,
+        JpegNeedMoreOutput = JxlDecoderStatus_JXL_DEC_JPEG_NEED_MORE_OUTPUT,
+        BoxNeedMoreOutput = JxlDecoderStatus_JXL_DEC_BOX_NEED_MORE_OUTPUT,
+        BasicInfo = JxlDecoderStatus_JXL_DEC_BASIC_INFO,
+        #[cfg(not(jxl_0_8))]
+        Extensions = JxlDecoderStatus_JXL_DEC_EXTENSIONS,
+        ColorEncoding = JxlDecoderStatus_JXL_DEC_COLOR_ENCODING,
+        PreviewImage = JxlDecoderStatus_JXL_DEC_PREVIEW_IMAGE,
+        Frame = JxlDecoderStatus_JXL_DEC_FRAME,
+        #[cfg(not(jxl_0_8))]
+        DcImage = JxlDecoderStatus_JXL_DEC_DC_IMAGE,
+        FullImage = JxlDecoderStatus_JXL_DEC_FULL_IMAGE,
+        JpegReconstruction = JxlDecoderStatus_JXL_DEC_JPEG_RECONSTRUCTION,
+        Box = JxlDecoderStatus_JXL_DEC_BOX,
+        FrameProgression = JxlDecoderStatus_JXL_DEC_FRAME_PROGRESSION,
+    });
+    assert_enum!(
+        enum JxlProgressiveDetail {
+            Frames = JxlProgressiveDetail_kFrames,
+            Dc = JxlProgressiveDetail_kDC,
+            LastPasses = JxlProgressiveDetail_kLastPasses,
+            Passes = JxlProgressiveDetail_kPasses,
+            DcProgressive = JxlProgressiveDetail_kDCProgressive,
+            DcGroups = JxlProgressiveDetail_kDCGroups,
+            Groups = JxlProgressiveDetail_kGroups,
+        }
+    );
+
+    assert_enum!(newtype JxlEncoderStatus {
+        Success = JxlEncoderStatus_JXL_ENC_SUCCESS,
+        Error = JxlEncoderStatus_JXL_ENC_ERROR,
+        NeedMoreOutput = JxlEncoderStatus_JXL_ENC_NEED_MORE_OUTPUT,
+        NotSupported = JxlEncoderStatus_JXL_ENC_NOT_SUPPORTED,
+    });
+    assert_enum!(
+        enum JxlEncoderFrameSettingId {
+            Effort = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_EFFORT,
+            DecodingSpeed = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_DECODING_SPEED,
+            Resampling = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_RESAMPLING,
+            ExtraChannelResampling =
+                JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_EXTRA_CHANNEL_RESAMPLING,
+            AlreadyDownsampled = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_ALREADY_DOWNSAMPLED,
+            PhotonNoise = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_PHOTON_NOISE,
+            Noise = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_NOISE,
+            Dots = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_DOTS,
+            Patches = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_PATCHES,
+            Epf = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_EPF,
+            Gaborish = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_GABORISH,
+            Modular = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_MODULAR,
+            KeepInvisible = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_KEEP_INVISIBLE,
+            GroupOrder = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_GROUP_ORDER,
+            GroupOrderCenterX = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_GROUP_ORDER_CENTER_X,
+            GroupOrderCenterY = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_GROUP_ORDER_CENTER_Y,
+            Responsive = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_RESPONSIVE,
+            ProgressiveAc = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_PROGRESSIVE_AC,
+            QprogressiveAc = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_QPROGRESSIVE_AC,
+            ProgressiveDc = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_PROGRESSIVE_DC,
+            ChannelColorsGlobalPercent =
+                JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_CHANNEL_COLORS_GLOBAL_PERCENT,
+            ChannelColorsGroupPercent =
+                JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_CHANNEL_COLORS_GROUP_PERCENT,
+            PaletteColors = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_PALETTE_COLORS,
+            LossyPalette = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_LOSSY_PALETTE,
+            ColorTransform = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_COLOR_TRANSFORM,
+            ModularColorSpace = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_MODULAR_COLOR_SPACE,
+            ModularGroupSize = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_MODULAR_GROUP_SIZE,
+            ModularPredictor = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_MODULAR_PREDICTOR,
+            ModularMaTreeLearningPercent =
+                JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_MODULAR_MA_TREE_LEARNING_PERCENT,
+            ModularNbPrevChannels =
+                JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_MODULAR_NB_PREV_CHANNELS,
+            JpegReconCfl = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_JPEG_RECON_CFL,
+            IndexBox = JxlEncoderFrameSettingId_JXL_ENC_FRAME_INDEX_BOX,
+            BrotliEffort = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_BROTLI_EFFORT,
+            #[cfg(jxl_0_8)]
+            JpegCompressBoxes = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_JPEG_COMPRESS_BOXES,
+            #[cfg(jxl_0_8)]
+            Buffering = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_BUFFERING,
+            #[cfg(jxl_0_8)]
+            JpegKeepExif = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_JPEG_KEEP_EXIF,
+            #[cfg(jxl_0_8)]
+            JpegKeepXmp = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_JPEG_KEEP_XMP,
+            #[cfg(jxl_0_8)]
+            JpegKeepJumbf = JxlEncoderFrameSettingId_JXL_ENC_FRAME_SETTING_JPEG_KEEP_JUMBF,
+        }
+    );
+}
@@ -0,0 +1,152 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/// Declare a C enum that libjxl writes as an integer newtype with an associated constant per
+/// value, so a value unknown to this crate (from a newer libjxl) is not undefined behavior.
+///
+/// Also declares a Rust enum of the known values, converted to with `TryFrom`, which returns the
+/// unknown value as the error.
+macro_rules! ffi_enum {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident => pub enum $rich:ident {
+            $( $(#[cfg($cfg:meta)])? $variant:ident = $value:expr ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr(transparent)]
+        #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name(pub ::std::os::raw::c_int);
+
+        #[allow(non_upper_case_globals)]
+        impl $name {
+            $(
+                $(#[cfg($cfg)])?
+                pub const $variant: Self = Self($value);
+            )*
+        }
+
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                match $rich::try_from(*self) {
+                    Ok(value) => ::std::fmt::Debug::fmt(&value, f),
+                    Err(_) => f.debug_tuple(stringify!($name)).field(&self.0).finish(),
+                }
+            }
+        }
+
+        #[doc = concat!("Known values of [`", stringify!($name), "`]")]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $rich {
+            $(
+                $(#[cfg($cfg)])?
+                $variant = $value,
+            )*
+        }
+
+        impl From<$rich> for $name {
+            fn from(value: $rich) -> Self {
+                Self(value as ::std::os::raw::c_int)
+            }
+        }
+
+        impl TryFrom<$name> for $rich {
+            type Error = $name;
+
+            fn try_from(value: $name) -> Result<Self, $name> {
+                match value {
+                    $(
+                        $(#[cfg($cfg)])?
+                        $name::$variant => Ok(Self::$variant),
+                    )*
+                    _ => Err(value),
+                }
+            }
+        }
+    };
+}
+
+/// Declare the functions of an `extern "C"` block.
+///
+/// With the `dynamic-loading` feature, declares instead a function forwarding to a pointer
+/// resolved at runtime, and a module `$module` whose `load` resolves every function of the block.
+macro_rules! extern_functions {
+    (
+        $(#[$block_meta:meta])*
+        mod $module:ident;
+
+        extern "C" {
+            $(
+                $(#[cfg($cfg:meta)])?
+                $(#[deprecated($($deprecated:tt)*)])?
+                pub fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;
+            )*
+        }
+    ) => {
+        #[cfg(not(feature = "dynamic-loading"))]
+        $(#[$block_meta])*
+        extern "C" {
+            $(
+                $(#[cfg($cfg)])?
+                $(#[deprecated($($deprecated)*)])?
+                pub fn $name($($arg: $ty),*) $(-> $ret)?;
+            )*
+        }
+
+        #[cfg(feature = "dynamic-loading")]
+        $(#[$block_meta])*
+        pub(crate) mod $module {
+            #![allow(non_snake_case, unused_imports, clippy::missing_safety_doc)]
+
+            use super::*;
+
+            pub(crate) struct Functions {
+                $(
+                    $(#[cfg($cfg)])?
+                    pub(crate) $name: unsafe extern "C" fn($($ty),*) $(-> $ret)?,
+                )*
+            }
+
+            pub(crate) static FUNCTIONS: ::std::sync::OnceLock<Functions> =
+                ::std::sync::OnceLock::new();
+
+            /// Resolve every function of the block
+            pub(crate) unsafe fn load(
+                library: &::libloading::Library,
+            ) -> Result<Functions, crate::dynamic::LoadError> {
+                Ok(Functions {
+                    $(
+                        $(#[cfg($cfg)])?
+                        $name: crate::dynamic::symbol(library, stringify!($name))?,
+                    )*
+                })
+            }
+
+            $(
+                $(#[cfg($cfg)])?
+                $(#[deprecated($($deprecated)*)])?
+                pub unsafe extern "C" fn $name($($arg: $ty),*) $(-> $ret)? {
+                    (crate::dynamic::functions(&FUNCTIONS).$name)($($arg),*)
+                }
+            )*
+        }
+
+        #[cfg(feature = "dynamic-loading")]
+        $(#[$block_meta])*
+        pub use self::$module::*;
+    };
+}
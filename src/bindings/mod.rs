@@ -15,11 +15,17 @@ You should have received a copy of the GNU General Public License
 along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+#[macro_use]
+mod macros;
+
 pub mod butteraugli;
 pub mod common;
 pub mod decoder;
 pub mod encoder;
 
+#[cfg(all(test, feature = "layout-tests"))]
+mod layout;
+
 pub use {common::*, decoder::*, encoder::*};
 
 #[cfg(feature = "threads")]
@@ -15,28 +15,39 @@ You should have received a copy of the GNU General Public License
 along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+// The generated functions name the C types by their full path
+#![cfg_attr(feature = "bindgen", allow(unused_imports))]
+
 use std::ffi::c_void;
 
 use crate::common::{
     JxlMemoryManager, JxlParallelRetCode, JxlParallelRunFunction, JxlParallelRunInit,
 };
 
-extern "C" {
-    pub fn JxlThreadParallelRunner(
-        runner_opaque: *mut c_void,
-        jpegxl_opaque: *mut c_void,
-        init: JxlParallelRunInit,
-        func: JxlParallelRunFunction,
-        start_range: u32,
-        end_range: u32,
-    ) -> JxlParallelRetCode;
+#[cfg(feature = "bindgen")]
+include!(concat!(env!("OUT_DIR"), "/bindings/parallel_runner.rs"));
+
+extern_functions! {
+    #[cfg(not(feature = "bindgen"))]
+    mod functions;
+
+    extern "C" {
+        pub fn JxlThreadParallelRunner(
+            runner_opaque: *mut c_void,
+            jpegxl_opaque: *mut c_void,
+            init: JxlParallelRunInit,
+            func: JxlParallelRunFunction,
+            start_range: u32,
+            end_range: u32,
+        ) -> JxlParallelRetCode;
 
-    pub fn JxlThreadParallelRunnerCreate(
-        memory_manager: *const JxlMemoryManager,
-        num_worker_threads: usize,
-    ) -> *mut c_void;
+        pub fn JxlThreadParallelRunnerCreate(
+            memory_manager: *const JxlMemoryManager,
+            num_worker_threads: usize,
+        ) -> *mut c_void;
 
-    pub fn JxlThreadParallelRunnerDestroy(runner_opaque: *mut c_void);
+        pub fn JxlThreadParallelRunnerDestroy(runner_opaque: *mut c_void);
 
-    pub fn JxlThreadParallelRunnerDefaultNumWorkerThreads() -> usize;
+        pub fn JxlThreadParallelRunnerDefaultNumWorkerThreads() -> usize;
+    }
 }
@@ -15,30 +15,51 @@ You should have received a copy of the GNU General Public License
 along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+// The generated functions name the C types by their full path
+#![cfg_attr(feature = "bindgen", allow(unused_imports))]
+
 use std::ffi::c_void;
 
 use crate::common::{
     JxlMemoryManager, JxlParallelRetCode, JxlParallelRunFunction, JxlParallelRunInit,
 };
 
-extern "C" {
-    pub fn JxlResizableParallelRunner(
-        runner_opaque: *mut c_void,
-        jpegxl_opaque: *mut c_void,
-        init: JxlParallelRunInit,
-        func: JxlParallelRunFunction,
-        start_range: u32,
-        end_range: u32,
-    ) -> JxlParallelRetCode;
+#[cfg(feature = "bindgen")]
+include!(concat!(
+    env!("OUT_DIR"),
+    "/bindings/resizable_parallel_runner.rs"
+));
+
+extern_functions! {
+    #[cfg(not(feature = "bindgen"))]
+    mod functions;
+
+    extern "C" {
+        pub fn JxlResizableParallelRunner(
+            runner_opaque: *mut c_void,
+            jpegxl_opaque: *mut c_void,
+            init: JxlParallelRunInit,
+            func: JxlParallelRunFunction,
+            start_range: u32,
+            end_range: u32,
+        ) -> JxlParallelRetCode;
 
-    pub fn JxlResizableParallelRunnerCreate(memory_manager: *const JxlMemoryManager)
-        -> *mut c_void;
+        pub fn JxlResizableParallelRunnerCreate(memory_manager: *const JxlMemoryManager)
+            -> *mut c_void;
 
-    pub fn JxlResizableParallelRunnerSetThreads(runner_opaque: *mut c_void, num_threads: usize);
+        pub fn JxlResizableParallelRunnerSetThreads(runner_opaque: *mut c_void, num_threads: usize);
 
-    pub fn JxlResizableParallelRunnerSuggestThreads(xsize: u64, ysize: u64) -> u32;
+        pub fn JxlResizableParallelRunnerSuggestThreads(xsize: u64, ysize: u64) -> u32;
+
+        pub fn JxlResizableParallelRunnerDestroy(runner_opaque: *mut c_void);
+    }
+}
 
-    pub fn JxlResizableParallelRunnerDestroy(runner_opaque: *mut c_void);
+// Declared in `thread_parallel_runner.h`, kept here for compatibility
+extern_functions! {
+    mod compat_functions;
 
-    pub fn JxlThreadParallelRunnerDefaultNumWorkerThreads() -> usize;
+    extern "C" {
+        pub fn JxlThreadParallelRunnerDefaultNumWorkerThreads() -> usize;
+    }
 }
@@ -0,0 +1,517 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Parsing the JPEG XL container, the ISOBMFF boxes around the codestream, without libjxl.
+//!
+//! # Examples
+//! ```
+//! # use jpegxl_sys::container::{BoxType, Container};
+//! let data = std::fs::read("test/sample_exif.jxl").unwrap();
+//! let container = Container::parse(&data).unwrap();
+//! for record in container.boxes() {
+//!     if record.box_type == BoxType::Exif {
+//!         println!("Exif: {} bytes", container.content(record).len());
+//!     }
+//! }
+//! let codestream = container.codestream().unwrap();
+//! ```
+
+use std::{borrow::Cow, error::Error, fmt, ops::Range};
+
+use crate::common::JxlSignature;
+
+/// Signature of a bare codestream
+const CODESTREAM_SIGNATURE: [u8; 2] = [0xff, 0x0a];
+
+/// The signature box starting every container
+const CONTAINER_SIGNATURE: [u8; 12] = [
+    0x00, 0x00, 0x00, 0x0c, b'J', b'X', b'L', b' ', 0x0d, 0x0a, 0x87, 0x0a,
+];
+
+/// Flag of the index of the last `jxlp` box
+const LAST_PART: u32 = 0x8000_0000;
+
+/// Check whether `data` starts like a codestream or a container, as `JxlSignatureCheck` does
+#[must_use]
+pub fn signature(data: &[u8]) -> JxlSignature {
+    let (expected, signature): (&[u8], _) = match data.first() {
+        None => return JxlSignature::NotEnoughBytes,
+        Some(0xff) => (&CODESTREAM_SIGNATURE, JxlSignature::Codestream),
+        Some(0x00) => (&CONTAINER_SIGNATURE, JxlSignature::Container),
+        Some(_) => return JxlSignature::Invalid,
+    };
+
+    // Like libjxl, only compared once long enough
+    match data.get(..expected.len()) {
+        None => JxlSignature::NotEnoughBytes,
+        Some(prefix) if prefix == expected => signature,
+        Some(_) => JxlSignature::Invalid,
+    }
+}
+
+/// Type of a box
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoxType {
+    /// `JXL `, the signature starting the container
+    Signature,
+    /// `ftyp`, the file type
+    FileType,
+    /// `jxll`, the level of the codestream
+    Level,
+    /// `jxlc`, the whole codestream
+    Codestream,
+    /// `jxlp`, a part of the codestream
+    PartialCodestream,
+    /// `jbrd`, data to reconstruct the original JPEG
+    JpegReconstruction,
+    /// `Exif`, Exif metadata, after a 4 byte offset to the TIFF header
+    Exif,
+    /// `xml `, XMP metadata
+    Xml,
+    /// `jumb`, JUMBF metadata
+    Jumbf,
+    /// `brob`, another box compressed with Brotli, whose type starts the content
+    Brotli,
+    /// Any other type
+    Other([u8; 4]),
+}
+
+impl BoxType {
+    /// The type of the four bytes in a box header
+    #[must_use]
+    pub fn from_bytes(bytes: [u8; 4]) -> Self {
+        match &bytes {
+            b"JXL " => Self::Signature,
+            b"ftyp" => Self::FileType,
+            b"jxll" => Self::Level,
+            b"jxlc" => Self::Codestream,
+            b"jxlp" => Self::PartialCodestream,
+            b"jbrd" => Self::JpegReconstruction,
+            b"Exif" => Self::Exif,
+            b"xml " => Self::Xml,
+            b"jumb" => Self::Jumbf,
+            b"brob" => Self::Brotli,
+            _ => Self::Other(bytes),
+        }
+    }
+
+    /// The four bytes of the type in a box header
+    #[must_use]
+    pub fn to_bytes(self) -> [u8; 4] {
+        match self {
+            Self::Signature => *b"JXL ",
+            Self::FileType => *b"ftyp",
+            Self::Level => *b"jxll",
+            Self::Codestream => *b"jxlc",
+            Self::PartialCodestream => *b"jxlp",
+            Self::JpegReconstruction => *b"jbrd",
+            Self::Exif => *b"Exif",
+            Self::Xml => *b"xml ",
+            Self::Jumbf => *b"jumb",
+            Self::Brotli => *b"brob",
+            Self::Other(bytes) => bytes,
+        }
+    }
+}
+
+/// A box of a container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoxRecord {
+    /// Type of the box
+    pub box_type: BoxType,
+    /// Offset of the box in the file
+    pub offset: usize,
+    /// Size of the header, 8 bytes, or 16 with a 64-bit size
+    pub header_size: usize,
+    /// Size of the box, header included
+    pub size: usize,
+}
+
+impl BoxRecord {
+    /// Range of the content in the file
+    #[must_use]
+    pub fn content_range(&self) -> Range<usize> {
+        self.offset + self.header_size..self.offset + self.size
+    }
+}
+
+/// Errors from parsing a container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerError {
+    /// The data is not a container, but what [`signature`] returned
+    NotContainer(JxlSignature),
+    /// The data ends in the box at this offset
+    Truncated(usize),
+    /// The size of the box at this offset is smaller than its header
+    InvalidSize(usize),
+    /// The container has no codestream
+    NoCodestream,
+    /// The container has both `jxlc` and `jxlp` boxes, or several `jxlc` boxes
+    MultipleCodestreams,
+    /// The `jxlp` box at this offset is out of order, or after the last one
+    InvalidPart(usize),
+    /// No `jxlp` box is marked as the last one
+    MissingLastPart,
+}
+
+impl fmt::Display for ContainerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotContainer(signature) => write!(f, "Not a container: {signature:?}"),
+            Self::Truncated(offset) => write!(f, "Data ends in the box at {offset}"),
+            Self::InvalidSize(offset) => write!(f, "Invalid size of the box at {offset}"),
+            Self::NoCodestream => write!(f, "No codestream in the container"),
+            Self::MultipleCodestreams => write!(f, "Several codestreams in the container"),
+            Self::InvalidPart(offset) => write!(f, "Invalid codestream part at {offset}"),
+            Self::MissingLastPart => write!(f, "Missing the last codestream part"),
+        }
+    }
+}
+
+impl Error for ContainerError {}
+
+/// A parsed container, borrowing the file
+#[derive(Debug, Clone)]
+pub struct Container<'a> {
+    data: &'a [u8],
+    boxes: Vec<BoxRecord>,
+}
+
+impl<'a> Container<'a> {
+    /// Parse the boxes of a whole container file
+    ///
+    /// # Errors
+    /// Returns an error if `data` is not a container, or a box header is invalid or ends past
+    /// `data`
+    pub fn parse(data: &'a [u8]) -> Result<Self, ContainerError> {
+        match signature(data) {
+            JxlSignature::Container => (),
+            JxlSignature::NotEnoughBytes => return Err(ContainerError::Truncated(0)),
+            signature => return Err(ContainerError::NotContainer(signature)),
+        }
+
+        let mut boxes = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let record = parse_box(data, offset)?;
+            offset += record.size;
+            boxes.push(record);
+        }
+
+        Ok(Self { data, boxes })
+    }
+
+    /// The boxes in file order
+    #[must_use]
+    pub fn boxes(&self) -> &[BoxRecord] {
+        &self.boxes
+    }
+
+    /// The content of a box of this container
+    #[must_use]
+    pub fn content(&self, record: &BoxRecord) -> &'a [u8] {
+        &self.data[record.content_range()]
+    }
+
+    /// The codestream, borrowed from a `jxlc` box, or reassembled from the `jxlp` boxes
+    ///
+    /// # Errors
+    /// Returns an error if there is no codestream, both kinds of boxes, or the `jxlp` boxes are
+    /// out of order or incomplete
+    pub fn codestream(&self) -> Result<Cow<'a, [u8]>, ContainerError> {
+        let mut whole = self
+            .boxes
+            .iter()
+            .filter(|record| record.box_type == BoxType::Codestream);
+        let parts: Vec<_> = self
+            .boxes
+            .iter()
+            .filter(|record| record.box_type == BoxType::PartialCodestream)
+            .collect();
+
+        match (whole.next(), whole.next(), parts.is_empty()) {
+            (Some(record), None, true) => return Ok(Cow::Borrowed(self.content(record))),
+            (Some(_), _, _) => return Err(ContainerError::MultipleCodestreams),
+            (None, _, true) => return Err(ContainerError::NoCodestream),
+            (None, _, false) => (),
+        }
+
+        let mut codestream = Vec::new();
+        let mut last = false;
+        for (index, record) in parts.into_iter().enumerate() {
+            let content = self.content(record);
+            let [a, b, c, d, ..] = *content else {
+                return Err(ContainerError::InvalidPart(record.offset));
+            };
+            let counter = u32::from_be_bytes([a, b, c, d]);
+            if last || usize::try_from(counter & !LAST_PART) != Ok(index) {
+                return Err(ContainerError::InvalidPart(record.offset));
+            }
+            last = counter & LAST_PART != 0;
+            codestream.extend_from_slice(&content[4..]);
+        }
+
+        if last {
+            Ok(Cow::Owned(codestream))
+        } else {
+            Err(ContainerError::MissingLastPart)
+        }
+    }
+}
+
+/// The start of the codestream in a container which may end anywhere
+pub(crate) struct PartialCodestream<'a> {
+    pub codestream: Cow<'a, [u8]>,
+    /// The end of the box the data ends in, if no codestream has started before it
+    pub skipped_box_end: Option<usize>,
+}
+
+/// The start of the codestream in `data`, which may end anywhere, from a `jxlc` box or the
+/// `jxlp` boxes in file order, to read the codestream headers before the whole file is available
+pub(crate) fn partial_codestream(data: &[u8]) -> Result<PartialCodestream<'_>, ContainerError> {
+    let mut codestream = Cow::Borrowed(&[][..]);
+    let mut skipped_box_end = None;
+    let mut offset = 0;
+    while offset < data.len() {
+        let record = match parse_header(data, offset) {
+            Err(ContainerError::Truncated(_)) => break,
+            record => record?,
+        };
+        let box_end = offset.saturating_add(record.size);
+        let end = data.len().min(box_end);
+        let content = &data[end.min(offset + record.header_size)..end];
+
+        match record.box_type {
+            BoxType::Codestream => {
+                return Ok(PartialCodestream {
+                    codestream: Cow::Borrowed(content),
+                    skipped_box_end: None,
+                })
+            }
+            BoxType::PartialCodestream => {
+                let part = content.get(4..).unwrap_or_default();
+                if codestream.is_empty() {
+                    codestream = Cow::Borrowed(part);
+                } else {
+                    codestream.to_mut().extend_from_slice(part);
+                }
+            }
+            _ if codestream.is_empty() && box_end > data.len() => {
+                skipped_box_end = Some(box_end);
+            }
+            _ => (),
+        }
+        offset = end;
+    }
+
+    Ok(PartialCodestream {
+        codestream,
+        skipped_box_end,
+    })
+}
+
+/// Parse the box at `offset`
+fn parse_box(data: &[u8], offset: usize) -> Result<BoxRecord, ContainerError> {
+    let record = parse_header(data, offset)?;
+    if record.size > data.len() - offset {
+        return Err(ContainerError::Truncated(offset));
+    }
+
+    Ok(record)
+}
+
+/// Parse the header of the box at `offset`, whose content may end past `data`
+fn parse_header(data: &[u8], offset: usize) -> Result<BoxRecord, ContainerError> {
+    let read = |start: usize, len: usize| {
+        data.get(offset + start..offset + start + len)
+            .ok_or(ContainerError::Truncated(offset))
+    };
+
+    let size = u32::from_be_bytes(read(0, 4)?.try_into().expect("4 bytes"));
+    let box_type = BoxType::from_bytes(read(4, 4)?.try_into().expect("4 bytes"));
+    let (header_size, size) = match size {
+        // Up to the end of the file
+        0 => (8, data.len() - offset),
+        1 => {
+            let size = u64::from_be_bytes(read(8, 8)?.try_into().expect("8 bytes"));
+            // Larger than the data anyway if it doesn't fit
+            (16, usize::try_from(size).unwrap_or(usize::MAX))
+        }
+        size => (8, size as usize),
+    };
+
+    if size < header_size {
+        return Err(ContainerError::InvalidSize(offset));
+    }
+
+    Ok(BoxRecord {
+        box_type,
+        offset,
+        header_size,
+        size,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::decoder::JxlSignatureCheck;
+
+    /// Box with a 32-bit size
+    fn make_box(box_type: [u8; 4], content: &[u8]) -> Vec<u8> {
+        let size = u32::try_from(content.len() + 8).unwrap();
+        [&size.to_be_bytes()[..], &box_type, content].concat()
+    }
+
+    #[test]
+    fn test_signature() {
+        let sample = std::fs::read("test/sample.jxl").unwrap();
+        let sample_exif = std::fs::read("test/sample_exif.jxl").unwrap();
+        let invalid: &[&[u8]] = &[
+            &[0xff, 0x0b],
+            &[0x00, 0x00, 0x00, 0x0d],
+            &[0x0a],
+            &CONTAINER_SIGNATURE[1..],
+        ];
+
+        for data in [&sample[..], &sample_exif[..]].iter().chain(invalid) {
+            for len in 0..=data.len().min(16) {
+                let expected = unsafe { JxlSignatureCheck(data.as_ptr(), len) };
+                assert_eq!(signature(&data[..len]), expected, "{:?}", &data[..len]);
+            }
+        }
+        assert_eq!(signature(&sample), JxlSignature::Codestream);
+        assert_eq!(signature(&sample_exif), JxlSignature::Container);
+    }
+
+    #[test]
+    fn test_parse() {
+        let sample = std::fs::read("test/sample.jxl").unwrap();
+        let sample_exif = std::fs::read("test/sample_exif.jxl").unwrap();
+
+        let container = Container::parse(&sample_exif).unwrap();
+        let types: Vec<_> = container.boxes().iter().map(|b| b.box_type).collect();
+        assert_eq!(
+            types,
+            [
+                BoxType::Signature,
+                BoxType::FileType,
+                BoxType::Exif,
+                BoxType::Codestream
+            ]
+        );
+        assert_eq!(
+            container.boxes()[2],
+            BoxRecord {
+                box_type: BoxType::Exif,
+                offset: 32,
+                header_size: 8,
+                size: 38
+            }
+        );
+        assert_eq!(&container.content(&container.boxes()[1])[..4], b"jxl ");
+        assert!(matches!(container.codestream(), Ok(Cow::Borrowed(c)) if c == sample));
+
+        assert_eq!(
+            Container::parse(&sample).unwrap_err(),
+            ContainerError::NotContainer(JxlSignature::Codestream)
+        );
+        assert_eq!(
+            Container::parse(&sample_exif[..100]).unwrap_err(),
+            ContainerError::Truncated(70)
+        );
+    }
+
+    #[test]
+    fn test_partial_codestream() {
+        let sample = std::fs::read("test/sample.jxl").unwrap();
+        let (first, rest) = sample.split_at(100);
+        let (second, third) = rest.split_at(1000);
+        let part = |counter: u32, data: &[u8]| {
+            make_box(*b"jxlp", &[&counter.to_be_bytes()[..], data].concat())
+        };
+
+        let mut data = CONTAINER_SIGNATURE.to_vec();
+        data.extend(make_box(*b"ftyp", b"jxl \0\0\0\0jxl "));
+        data.extend(part(0, first));
+        data.extend(make_box(*b"xml ", b"<x:xmpmeta/>"));
+        // With a 64-bit size
+        let size = u64::try_from(16 + 4 + second.len()).unwrap();
+        data.extend([&1u32.to_be_bytes()[..], b"jxlp", &size.to_be_bytes()].concat());
+        data.extend(1u32.to_be_bytes());
+        data.extend(second);
+        // Up to the end of the file
+        let last_offset = data.len();
+        data.extend([&0u32.to_be_bytes()[..], b"jxlp"].concat());
+        data.extend((LAST_PART | 2).to_be_bytes());
+        data.extend(third);
+
+        assert_eq!(
+            unsafe { JxlSignatureCheck(data.as_ptr(), data.len()) },
+            JxlSignature::Container
+        );
+        let container = Container::parse(&data).unwrap();
+        let boxes = container.boxes();
+        assert_eq!(boxes.len(), 6);
+        assert_eq!(boxes[4].header_size, 16);
+        assert_eq!(boxes[4].size, 16 + 4 + second.len());
+        assert_eq!(boxes[5].offset, last_offset);
+        assert_eq!(boxes[5].size, data.len() - last_offset);
+        assert!(matches!(container.codestream(), Ok(Cow::Owned(c)) if c == sample));
+
+        let container = Container::parse(&data[..last_offset]).unwrap();
+        assert_eq!(
+            container.codestream().unwrap_err(),
+            ContainerError::MissingLastPart
+        );
+
+        let mut data = data[..last_offset].to_vec();
+        data.extend(part(LAST_PART | 3, third));
+        let container = Container::parse(&data).unwrap();
+        assert_eq!(
+            container.codestream().unwrap_err(),
+            ContainerError::InvalidPart(last_offset)
+        );
+
+        data.truncate(last_offset);
+        data.extend(make_box(*b"jxlc", &sample));
+        let container = Container::parse(&data).unwrap();
+        assert_eq!(
+            container.codestream().unwrap_err(),
+            ContainerError::MultipleCodestreams
+        );
+    }
+
+    #[test]
+    fn test_invalid_size() {
+        let mut data = CONTAINER_SIGNATURE.to_vec();
+        data.extend([0, 0, 0, 4]);
+        data.extend(b"ftyp");
+        assert_eq!(
+            Container::parse(&data).unwrap_err(),
+            ContainerError::InvalidSize(12)
+        );
+        assert_eq!(
+            Container::parse(&data[..14]).unwrap_err(),
+            ContainerError::Truncated(12)
+        );
+        assert_eq!(
+            Container::parse(&CONTAINER_SIGNATURE).unwrap().codestream(),
+            Err(ContainerError::NoCodestream)
+        );
+    }
+}
@@ -0,0 +1,266 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Loading libjxl at runtime instead of linking it, with the `dynamic-loading` feature.
+//!
+//! The functions of the bindings load the library on their first call, and abort the process if
+//! that fails. Call [`load`] first to handle a missing library instead, for example by disabling
+//! JPEG XL support.
+
+use std::{fmt, sync::OnceLock};
+
+use libloading::Library;
+
+/// Versions of libjxl the bindings were built for, checked against `JxlDecoderVersion`
+#[cfg(not(jxl_0_8))]
+const EXPECTED_VERSION: &str = "0.7";
+#[cfg(jxl_0_8)]
+const EXPECTED_VERSION: &str = "0.8";
+
+/// Error loading libjxl
+#[derive(Debug)]
+pub enum LoadError {
+    /// The library could not be opened under any of its file names
+    Library {
+        name: &'static str,
+        source: libloading::Error,
+    },
+    /// The library lacks a function of the bindings
+    Symbol {
+        name: &'static str,
+        source: libloading::Error,
+    },
+    /// The library is another version than the bindings were built for
+    Version(u32),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Library { name, source } => write!(f, "Failed to load lib{name}: {source}"),
+            Self::Symbol { name, source } => {
+                write!(f, "Failed to find {name} in libjxl: {source}")
+            }
+            Self::Version(version) => write!(
+                f,
+                "Loaded libjxl {}.{}.{}, but the bindings need {EXPECTED_VERSION}",
+                version / 1_000_000,
+                version / 1000 % 1000,
+                version % 1000
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Library { source, .. } | Self::Symbol { source, .. } => Some(source),
+            Self::Version(_) => None,
+        }
+    }
+}
+
+/// Libraries kept loaded for the lifetime of the process
+#[allow(dead_code)]
+struct Libraries {
+    jxl: Library,
+    #[cfg(feature = "threads")]
+    threads: Library,
+}
+
+static LIBRARIES: OnceLock<Result<Libraries, LoadError>> = OnceLock::new();
+
+/// Load libjxl, and `libjxl_threads` with the `threads` feature, unless already loaded.
+///
+/// # Errors
+/// Returns the error of the first attempt if a library or one of its functions is missing, or
+/// libjxl is another version than the bindings were built for
+pub fn load() -> Result<(), &'static LoadError> {
+    LIBRARIES
+        .get_or_init(|| unsafe { load_libraries() })
+        .as_ref()
+        .map(|_| ())
+}
+
+/// Whether libjxl is loaded
+#[must_use]
+pub fn is_loaded() -> bool {
+    matches!(LIBRARIES.get(), Some(Ok(_)))
+}
+
+unsafe fn load_libraries() -> Result<Libraries, LoadError> {
+    use crate::{butteraugli, decoder, encoder};
+
+    // Checks the version before resolving the rest, as another version may lack some functions
+    let jxl = open("jxl", |library| {
+        let version: unsafe extern "C" fn() -> u32 = symbol(library, "JxlDecoderVersion")?;
+        check_version(version())
+    })?;
+
+    let decoder_functions = decoder::functions::load(&jxl)?;
+    let decoder_precise_functions = decoder::precise_functions::load(&jxl)?;
+    let encoder_functions = encoder::functions::load(&jxl)?;
+    let encoder_precise_functions = encoder::precise_functions::load(&jxl)?;
+    let butteraugli_functions = butteraugli::functions::load(&jxl)?;
+    let butteraugli_precise_functions = butteraugli::precise_functions::load(&jxl)?;
+
+    #[cfg(feature = "threads")]
+    let (threads, runner_functions) = {
+        use crate::{parallel_runner, resizable_parallel_runner};
+
+        let threads = open("jxl_threads", |_| Ok(()))?;
+        let functions = (
+            parallel_runner::functions::load(&threads)?,
+            resizable_parallel_runner::functions::load(&threads)?,
+            resizable_parallel_runner::compat_functions::load(&threads)?,
+        );
+        (threads, functions)
+    };
+
+    // Only publish the functions once all of them are resolved
+    let _ = decoder::functions::FUNCTIONS.set(decoder_functions);
+    let _ = decoder::precise_functions::FUNCTIONS.set(decoder_precise_functions);
+    let _ = encoder::functions::FUNCTIONS.set(encoder_functions);
+    let _ = encoder::precise_functions::FUNCTIONS.set(encoder_precise_functions);
+    let _ = butteraugli::functions::FUNCTIONS.set(butteraugli_functions);
+    let _ = butteraugli::precise_functions::FUNCTIONS.set(butteraugli_precise_functions);
+    #[cfg(feature = "threads")]
+    {
+        use crate::{parallel_runner, resizable_parallel_runner};
+
+        let _ = parallel_runner::functions::FUNCTIONS.set(runner_functions.0);
+        let _ = resizable_parallel_runner::functions::FUNCTIONS.set(runner_functions.1);
+        let _ = resizable_parallel_runner::compat_functions::FUNCTIONS.set(runner_functions.2);
+    }
+
+    Ok(Libraries {
+        jxl,
+        #[cfg(feature = "threads")]
+        threads,
+    })
+}
+
+/// Open the first library accepted by `check`, by its platform file name, or on Linux by the
+/// name of the version the bindings were built for, as the development symlink may be missing
+/// or point to another version
+unsafe fn open(
+    name: &'static str,
+    check: impl Fn(&Library) -> Result<(), LoadError>,
+) -> Result<Library, LoadError> {
+    let mut file_names = vec![libloading::library_filename(name)];
+    #[cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
+    {
+        #[cfg(not(jxl_0_8))]
+        let version = "0.7";
+        #[cfg(jxl_0_8)]
+        let version = "0.8";
+        file_names.push(format!("lib{name}.so.{version}").into());
+    }
+
+    let mut open_error = None;
+    let mut check_error = None;
+    for file_name in file_names {
+        match Library::new(file_name) {
+            Ok(library) => match check(&library) {
+                Ok(()) => return Ok(library),
+                Err(error) => check_error = check_error.or(Some(error)),
+            },
+            Err(source) => open_error = open_error.or(Some(source)),
+        }
+    }
+
+    // A library of another version is the more useful error
+    Err(check_error.unwrap_or_else(|| LoadError::Library {
+        name,
+        source: open_error.expect("At least one file name"),
+    }))
+}
+
+fn check_version(version: u32) -> Result<(), LoadError> {
+    let (major, minor) = (version / 1_000_000, version / 1000 % 1000);
+
+    #[cfg(not(jxl_0_8))]
+    let supported = major == 0 && minor == 7;
+    #[cfg(jxl_0_8)]
+    let supported = major == 0 && minor == 8;
+
+    if supported {
+        Ok(())
+    } else {
+        Err(LoadError::Version(version))
+    }
+}
+
+pub(crate) unsafe fn symbol<T: Copy>(
+    library: &Library,
+    name: &'static str,
+) -> Result<T, LoadError> {
+    library
+        .get::<T>(name.as_bytes())
+        .map(|symbol| *symbol)
+        .map_err(|source| LoadError::Symbol { name, source })
+}
+
+/// The functions of a block, loading libjxl on the first call
+pub(crate) fn functions<T>(functions: &'static OnceLock<T>) -> &'static T {
+    functions.get().unwrap_or_else(|| {
+        if let Err(e) = load() {
+            panic!("{e}");
+        }
+        functions.get().expect("All functions are set once loaded")
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        load().unwrap();
+        assert!(is_loaded());
+        unsafe {
+            assert!(check_version(crate::JxlDecoderVersion()).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_check_version() {
+        assert!(matches!(check_version(6001), Err(LoadError::Version(6001))));
+
+        #[cfg(not(jxl_0_8))]
+        {
+            assert!(check_version(7000).is_ok());
+            assert!(check_version(8000).is_err());
+        }
+        #[cfg(jxl_0_8)]
+        {
+            assert!(check_version(7000).is_err());
+            assert!(check_version(8000).is_ok());
+            assert!(check_version(8002).is_ok());
+            // Later versions changed signatures and layouts
+            assert!(check_version(9000).is_err());
+            assert!(check_version(1_000_000).is_err());
+        }
+
+        assert_eq!(
+            LoadError::Version(6001).to_string(),
+            format!("Loaded libjxl 0.6.1, but the bindings need {EXPECTED_VERSION}")
+        );
+    }
+}
@@ -0,0 +1,718 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Reading the [`JxlBasicInfo`] from the codestream headers without libjxl, from the first bytes
+//! of a file.
+//!
+//! # Examples
+//! ```
+//! # use jpegxl_sys::header::{basic_info, HeaderError};
+//! let data = std::fs::read("test/sample.jxl").unwrap();
+//! match basic_info(&data[..10], false) {
+//!     Ok(info) => println!("{}x{}", info.xsize, info.ysize),
+//!     Err(HeaderError::NeedMoreInput(size)) => println!("Read {size} bytes and try again"),
+//!     Err(e) => println!("Invalid file: {e}"),
+//! }
+//! ```
+
+use std::{error::Error, fmt, mem::MaybeUninit, os::raw::c_int};
+
+use crate::{
+    common::{JxlBasicInfo, JxlOrientation, JxlSignature},
+    container::{self, ContainerError},
+};
+
+/// Input size `JxlDecoderSizeHintBasicInfo` suggests before any input: the boxes before the
+/// codestream in a container, and the basic info of a usual codestream
+pub const INITIAL_SIZE_HINT: usize = 98;
+
+/// Size of the basic info of a usual codestream, asked for past the input
+const BASIC_INFO_SIZE_HINT: usize = 50;
+
+/// `ExtraChannelType` of an alpha channel
+const ALPHA: u32 = 0;
+
+/// Errors from reading the headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderError {
+    /// The input ends before the basic info. Like `JxlDecoderSizeHintBasicInfo`, a suggested
+    /// size of the whole input to try again with, which is not a bound
+    NeedMoreInput(usize),
+    /// The input is neither a codestream nor a container
+    InvalidSignature,
+    /// The container is invalid
+    Container(ContainerError),
+    /// A header field has an invalid value
+    InvalidField(&'static str),
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NeedMoreInput(size) => write!(f, "Input ends before the basic info, try {size}"),
+            Self::InvalidSignature => write!(f, "Not a JPEG XL codestream or container"),
+            Self::Container(e) => write!(f, "Invalid container: {e}"),
+            Self::InvalidField(field) => write!(f, "Invalid {field} in the headers"),
+        }
+    }
+}
+
+impl Error for HeaderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Container(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ContainerError> for HeaderError {
+    fn from(e: ContainerError) -> Self {
+        Self::Container(e)
+    }
+}
+
+/// Read the basic info from the `SizeHeader` and `ImageMetadata` at the start of a codestream,
+/// bare or in a container, as `JxlDecoderGetBasicInfo` returns it.
+///
+/// With `keep_orientation` false, the default of `JxlDecoderSetKeepOrientation`, the sizes are
+/// those of the image once oriented, and the orientation is `Identity`.
+///
+/// # Errors
+/// Returns [`HeaderError::NeedMoreInput`] if `data` ends before the basic info, or another error
+/// if it is invalid
+pub fn basic_info(data: &[u8], keep_orientation: bool) -> Result<JxlBasicInfo, HeaderError> {
+    let mut hint = size_hint(data.len());
+
+    let (codestream, have_container) = match container::signature(data) {
+        JxlSignature::Codestream => (data.into(), false),
+        JxlSignature::Container => {
+            let partial = container::partial_codestream(data)?;
+            // The box before the codestream has to be skipped first, no less than initially
+            if let Some(end) = partial.skipped_box_end {
+                hint = hint.max(end.saturating_add(BASIC_INFO_SIZE_HINT));
+            }
+            (partial.codestream, true)
+        }
+        JxlSignature::NotEnoughBytes => return Err(HeaderError::NeedMoreInput(hint)),
+        _ => return Err(HeaderError::InvalidSignature),
+    };
+
+    match codestream.get(..2) {
+        Some([0xff, 0x0a]) => (),
+        Some(_) => return Err(HeaderError::InvalidSignature),
+        None => return Err(HeaderError::NeedMoreInput(hint)),
+    }
+
+    let mut reader = BitReader::new(&codestream[2..]);
+    match read_basic_info(&mut reader, have_container, keep_orientation) {
+        Ok(info) => Ok(info),
+        Err(ReadError::OutOfBounds) => Err(HeaderError::NeedMoreInput(hint)),
+        Err(ReadError::Invalid(field)) => Err(HeaderError::InvalidField(field)),
+    }
+}
+
+/// Input size to try after `len` bytes were not enough
+fn size_hint(len: usize) -> usize {
+    INITIAL_SIZE_HINT.max(len + BASIC_INFO_SIZE_HINT)
+}
+
+enum ReadError {
+    OutOfBounds,
+    Invalid(&'static str),
+}
+
+/// Distribution of a `U32` field
+#[derive(Clone, Copy)]
+enum Dist {
+    Val(u32),
+    /// Bits and offset
+    Bits(u32, u32),
+}
+
+use Dist::{Bits, Val};
+
+/// Reader of the bits of the headers, from the least significant bit of each byte
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    /// Read `n` bits, at most 32
+    fn bits(&mut self, n: u32) -> Result<u32, ReadError> {
+        let mut value = 0;
+        for i in 0..n {
+            let byte = self
+                .data
+                .get(self.position / 8)
+                .ok_or(ReadError::OutOfBounds)?;
+            value |= u32::from(byte >> (self.position % 8) & 1) << i;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    fn bool(&mut self) -> Result<bool, ReadError> {
+        Ok(self.bits(1)? == 1)
+    }
+
+    fn u32(&mut self, dists: [Dist; 4]) -> Result<u32, ReadError> {
+        match dists[self.bits(2)? as usize] {
+            Val(value) => Ok(value),
+            Bits(n, offset) => Ok(self.bits(n)?.wrapping_add(offset)),
+        }
+    }
+
+    fn f16(&mut self) -> Result<f32, ReadError> {
+        let bits = self.bits(16)?;
+        let sign = if bits >> 15 == 1 { -1.0 } else { 1.0 };
+        let exponent = bits >> 10 & 0x1f;
+        let mantissa = bits & 0x3ff;
+        match exponent {
+            0x1f => Err(ReadError::Invalid("infinite or NaN float")),
+            0 => Ok(sign * f32::from(u16::try_from(mantissa).unwrap_or_default()) / 16_777_216.0),
+            _ => Ok(sign * f32::from_bits((exponent + 112) << 23 | mantissa << 13)),
+        }
+    }
+
+    /// Read an enum, checking it is one of `valid`
+    fn enumeration(&mut self, valid: &[u32], name: &'static str) -> Result<u32, ReadError> {
+        let value = self.u32([Val(0), Val(1), Bits(4, 2), Bits(6, 18)])?;
+        if valid.contains(&value) {
+            Ok(value)
+        } else {
+            Err(ReadError::Invalid(name))
+        }
+    }
+}
+
+/// Read the `SizeHeader`, and the `ImageMetadata` up to the tone mapping
+fn read_basic_info(
+    reader: &mut BitReader,
+    have_container: bool,
+    keep_orientation: bool,
+) -> Result<JxlBasicInfo, ReadError> {
+    // SAFETY: Every field is an integer, a float, or a struct of them
+    let mut info: JxlBasicInfo = unsafe { MaybeUninit::zeroed().assume_init() };
+    info.have_container = have_container.into();
+    (info.xsize, info.ysize) = read_size_header(reader)?;
+    (info.intrinsic_xsize, info.intrinsic_ysize) = (info.xsize, info.ysize);
+
+    // Defaults of an all-default `ImageMetadata`
+    let mut orientation = 1;
+    info.bits_per_sample = 8;
+    info.intensity_target = 255.0;
+    info.num_color_channels = 3;
+
+    let all_default = reader.bool()?;
+    let xyb_encoded = if all_default {
+        true
+    } else {
+        let extra_fields = reader.bool()?;
+        if extra_fields {
+            orientation = reader.bits(3)? + 1;
+            if reader.bool()? {
+                (info.intrinsic_xsize, info.intrinsic_ysize) = read_size_header(reader)?;
+            }
+            info.have_preview = reader.bool()?.into();
+            if info.have_preview.into() {
+                read_preview_header(reader, &mut info)?;
+            }
+            info.have_animation = reader.bool()?.into();
+            if info.have_animation.into() {
+                read_animation_header(reader, &mut info)?;
+            }
+        }
+
+        (info.bits_per_sample, info.exponent_bits_per_sample) = read_bit_depth(reader)?;
+        let _modular_16_bit_buffer_sufficient = reader.bool()?;
+
+        info.num_extra_channels = reader.u32([Val(0), Val(1), Bits(4, 2), Bits(12, 1)])?;
+        let mut have_alpha = false;
+        for _ in 0..info.num_extra_channels {
+            let channel = read_extra_channel_info(reader)?;
+            if channel.type_ == ALPHA && !have_alpha {
+                have_alpha = true;
+                info.alpha_bits = channel.bits_per_sample;
+                info.alpha_exponent_bits = channel.exponent_bits_per_sample;
+                info.alpha_premultiplied = channel.alpha_associated.into();
+            }
+        }
+
+        let xyb_encoded = reader.bool()?;
+        if read_color_encoding(reader)? {
+            info.num_color_channels = 1;
+        }
+        if extra_fields {
+            read_tone_mapping(reader, &mut info)?;
+        }
+        xyb_encoded
+    };
+    info.uses_original_profile = (!xyb_encoded).into();
+
+    if keep_orientation {
+        info.orientation = JxlOrientation(c_int::try_from(orientation).unwrap_or_default());
+    } else {
+        // Transposing orientations
+        if orientation > 4 {
+            std::mem::swap(&mut info.xsize, &mut info.ysize);
+            std::mem::swap(&mut info.preview.xsize, &mut info.preview.ysize);
+            std::mem::swap(&mut info.intrinsic_xsize, &mut info.intrinsic_ysize);
+        }
+        info.orientation = JxlOrientation::Identity;
+    }
+
+    Ok(info)
+}
+
+/// Width of an image of `ysize` with the aspect `ratio` of a size header
+fn aspect_xsize(ysize: u32, ratio: u32) -> u32 {
+    let (numerator, denominator) = match ratio {
+        1 => (1, 1),
+        2 => (12, 10),
+        3 => (4, 3),
+        4 => (3, 2),
+        5 => (16, 9),
+        6 => (5, 4),
+        _ => (2, 1),
+    };
+    u32::try_from(u64::from(ysize) * numerator / denominator).unwrap_or(u32::MAX)
+}
+
+fn read_size_header(reader: &mut BitReader) -> Result<(u32, u32), ReadError> {
+    const DIST: [Dist; 4] = [Bits(9, 1), Bits(13, 1), Bits(18, 1), Bits(30, 1)];
+
+    let small = reader.bool()?;
+    let read_size = |reader: &mut BitReader| {
+        if small {
+            Ok((reader.bits(5)? + 1) * 8)
+        } else {
+            reader.u32(DIST)
+        }
+    };
+    let ysize = read_size(reader)?;
+    let xsize = match reader.bits(3)? {
+        0 => read_size(reader)?,
+        ratio => aspect_xsize(ysize, ratio),
+    };
+
+    Ok((xsize, ysize))
+}
+
+fn read_preview_header(reader: &mut BitReader, info: &mut JxlBasicInfo) -> Result<(), ReadError> {
+    const DIV8_DIST: [Dist; 4] = [Val(16), Val(32), Bits(5, 1), Bits(9, 33)];
+    const DIST: [Dist; 4] = [Bits(6, 1), Bits(8, 65), Bits(10, 321), Bits(12, 1345)];
+
+    let div8 = reader.bool()?;
+    let read_size = |reader: &mut BitReader| {
+        if div8 {
+            Ok(reader.u32(DIV8_DIST)? * 8)
+        } else {
+            reader.u32(DIST)
+        }
+    };
+    info.preview.ysize = read_size(reader)?;
+    info.preview.xsize = match reader.bits(3)? {
+        0 => read_size(reader)?,
+        ratio => aspect_xsize(info.preview.ysize, ratio),
+    };
+
+    Ok(())
+}
+
+fn read_animation_header(reader: &mut BitReader, info: &mut JxlBasicInfo) -> Result<(), ReadError> {
+    let animation = &mut info.animation;
+    animation.tps_numerator = reader.u32([Val(100), Val(1000), Bits(10, 1), Bits(30, 1)])?;
+    animation.tps_denominator = reader.u32([Val(1), Val(1001), Bits(8, 1), Bits(10, 1)])?;
+    animation.num_loops = reader.u32([Val(0), Bits(3, 0), Bits(16, 0), Bits(32, 0)])?;
+    animation.have_timecodes = reader.bool()?.into();
+
+    Ok(())
+}
+
+/// Bits per sample and exponent bits of a `BitDepth`
+fn read_bit_depth(reader: &mut BitReader) -> Result<(u32, u32), ReadError> {
+    if reader.bool()? {
+        let bits = reader.u32([Val(32), Val(16), Val(24), Bits(6, 1)])?;
+        let exponent_bits = reader.bits(4)? + 1;
+        let mantissa_bits = bits.wrapping_sub(exponent_bits).wrapping_sub(1);
+        if !(2..=8).contains(&exponent_bits) || !(2..=23).contains(&mantissa_bits) {
+            return Err(ReadError::Invalid("float bit depth"));
+        }
+        Ok((bits, exponent_bits))
+    } else {
+        let bits = reader.u32([Val(8), Val(10), Val(12), Bits(6, 1)])?;
+        if bits > 31 {
+            return Err(ReadError::Invalid("bit depth"));
+        }
+        Ok((bits, 0))
+    }
+}
+
+/// The fields of an `ExtraChannelInfo` in the basic info
+struct ExtraChannel {
+    type_: u32,
+    bits_per_sample: u32,
+    exponent_bits_per_sample: u32,
+    alpha_associated: bool,
+}
+
+fn read_extra_channel_info(reader: &mut BitReader) -> Result<ExtraChannel, ReadError> {
+    const SPOT_COLOR: u32 = 2;
+    const CFA: u32 = 5;
+
+    if reader.bool()? {
+        return Ok(ExtraChannel {
+            type_: ALPHA,
+            bits_per_sample: 8,
+            exponent_bits_per_sample: 0,
+            alpha_associated: false,
+        });
+    }
+
+    // Up to `kCFA`, then `kUnknown` and `kOptional`
+    let type_ = reader.enumeration(&[0, 1, 2, 3, 4, 5, 6, 15, 16], "extra channel type")?;
+    let (bits_per_sample, exponent_bits_per_sample) = read_bit_depth(reader)?;
+    let _dim_shift = reader.u32([Val(0), Val(3), Val(4), Bits(3, 1)])?;
+    let name_length = reader.u32([Val(0), Bits(4, 0), Bits(5, 16), Bits(10, 48)])?;
+    for _ in 0..name_length {
+        reader.bits(8)?;
+    }
+
+    let alpha_associated = type_ == ALPHA && reader.bool()?;
+    if type_ == SPOT_COLOR {
+        for _ in 0..4 {
+            reader.f16()?;
+        }
+    }
+    if type_ == CFA {
+        reader.u32([Val(1), Bits(2, 0), Bits(4, 3), Bits(8, 19)])?;
+    }
+
+    Ok(ExtraChannel {
+        type_,
+        bits_per_sample,
+        exponent_bits_per_sample,
+        alpha_associated,
+    })
+}
+
+/// Read a `ColorEncoding`, returning whether it is grayscale
+fn read_color_encoding(reader: &mut BitReader) -> Result<bool, ReadError> {
+    const GRAY: u32 = 1;
+    const XYB: u32 = 2;
+    const CUSTOM: u32 = 2;
+    const GAMMA_MUL: u32 = 10_000_000;
+
+    if reader.bool()? {
+        return Ok(false);
+    }
+
+    let want_icc = reader.bool()?;
+    let color_space = reader.enumeration(&[0, 1, 2, 3], "color space")?;
+    if !want_icc {
+        let read_custom_xy = |reader: &mut BitReader| {
+            for _ in 0..2 {
+                reader.u32([
+                    Bits(19, 0),
+                    Bits(19, 524_288),
+                    Bits(20, 1_048_576),
+                    Bits(21, 2_097_152),
+                ])?;
+            }
+            Ok(())
+        };
+
+        if color_space != XYB && reader.enumeration(&[1, 2, 10, 11], "white point")? == CUSTOM {
+            read_custom_xy(reader)?;
+        }
+        if color_space != GRAY
+            && color_space != XYB
+            && reader.enumeration(&[1, 2, 9, 11], "primaries")? == CUSTOM
+        {
+            for _ in 0..3 {
+                read_custom_xy(reader)?;
+            }
+        }
+        // Implicit for XYB
+        if color_space != XYB {
+            if reader.bool()? {
+                let gamma = reader.bits(24)?;
+                if gamma > GAMMA_MUL || u64::from(gamma) * 8192 < u64::from(GAMMA_MUL) {
+                    return Err(ReadError::Invalid("gamma"));
+                }
+            } else {
+                reader.enumeration(&[1, 2, 8, 13, 16, 17, 18], "transfer function")?;
+            }
+        }
+        reader.enumeration(&[0, 1, 2, 3], "rendering intent")?;
+    }
+
+    Ok(color_space == GRAY)
+}
+
+fn read_tone_mapping(reader: &mut BitReader, info: &mut JxlBasicInfo) -> Result<(), ReadError> {
+    if reader.bool()? {
+        return Ok(());
+    }
+
+    info.intensity_target = reader.f16()?;
+    if info.intensity_target <= 0.0 {
+        return Err(ReadError::Invalid("intensity target"));
+    }
+    info.min_nits = reader.f16()?;
+    if info.min_nits < 0.0 || info.min_nits > info.intensity_target {
+        return Err(ReadError::Invalid("minimum nits"));
+    }
+    let relative_to_max_display = reader.bool()?;
+    info.relative_to_max_display = relative_to_max_display.into();
+    info.linear_below = reader.f16()?;
+    if info.linear_below < 0.0 || (relative_to_max_display && info.linear_below > 1.0) {
+        return Err(ReadError::Invalid("linear below"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::decoder::*;
+    use std::ptr;
+
+    /// The basic info of libjxl after `data`, or `None` if it needs more input
+    fn libjxl_basic_info(data: &[u8], keep_orientation: bool) -> Option<JxlBasicInfo> {
+        unsafe {
+            let dec = JxlDecoderCreate(ptr::null());
+            assert_eq!(
+                JxlDecoderSubscribeEvents(dec, JxlDecoderEvents::BASIC_INFO),
+                JxlDecoderStatus::Success
+            );
+            JxlDecoderSetKeepOrientation(dec, keep_orientation);
+            JxlDecoderSetInput(dec, data.as_ptr(), data.len());
+
+            let info = match JxlDecoderProcessInput(dec) {
+                JxlDecoderStatus::BasicInfo => {
+                    // Zeroed like ours, as libjxl leaves the padding alone
+                    let mut info = MaybeUninit::zeroed();
+                    assert_eq!(
+                        JxlDecoderGetBasicInfo(dec, info.as_mut_ptr()),
+                        JxlDecoderStatus::Success
+                    );
+                    Some(info.assume_init())
+                }
+                JxlDecoderStatus::NeedMoreInput => None,
+                status => panic!("Unexpected status: {status:?}"),
+            };
+            JxlDecoderDestroy(dec);
+            info
+        }
+    }
+
+    /// The size hint of libjxl after `data`, which must not be enough
+    fn libjxl_size_hint(data: &[u8]) -> usize {
+        unsafe {
+            let dec = JxlDecoderCreate(ptr::null());
+            assert_eq!(
+                JxlDecoderSubscribeEvents(dec, JxlDecoderEvents::BASIC_INFO),
+                JxlDecoderStatus::Success
+            );
+            JxlDecoderSetInput(dec, data.as_ptr(), data.len());
+            assert_eq!(JxlDecoderProcessInput(dec), JxlDecoderStatus::NeedMoreInput);
+            let hint = JxlDecoderSizeHintBasicInfo(dec);
+            JxlDecoderDestroy(dec);
+            hint
+        }
+    }
+
+    #[test]
+    fn test_basic_info() {
+        for file in ["test/sample.jxl", "test/sample_exif.jxl"] {
+            let data = std::fs::read(file).unwrap();
+            let info = basic_info(&data, false).unwrap();
+            assert_eq!((info.xsize, info.ysize), (40, 50));
+            assert_eq!(bool::from(info.have_container), file.contains("exif"));
+
+            for keep_orientation in [false, true] {
+                assert_eq!(
+                    Some(basic_info(&data, keep_orientation).unwrap()),
+                    libjxl_basic_info(&data, keep_orientation),
+                    "{file}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_size_hint() {
+        unsafe {
+            let dec = JxlDecoderCreate(ptr::null());
+            assert_eq!(JxlDecoderSizeHintBasicInfo(dec), INITIAL_SIZE_HINT);
+            JxlDecoderDestroy(dec);
+        }
+        assert_eq!(
+            basic_info(&[], false),
+            Err(HeaderError::NeedMoreInput(INITIAL_SIZE_HINT))
+        );
+
+        for file in ["test/sample.jxl", "test/sample_exif.jxl"] {
+            let data = std::fs::read(file).unwrap();
+            for len in 0..200 {
+                let prefix = &data[..len];
+                match basic_info(prefix, false) {
+                    Ok(info) => assert_eq!(Some(info), libjxl_basic_info(prefix, false)),
+                    Err(HeaderError::NeedMoreInput(hint)) => {
+                        assert_eq!(libjxl_basic_info(prefix, false), None, "{file}: {len}");
+                        assert!(basic_info(&data[..hint], false).is_ok(), "{file}: {len}");
+                    }
+                    Err(e) => panic!("{file}: {len}: {e}"),
+                }
+            }
+        }
+
+        // Inside the Exif box before the codestream, which ends at 70
+        let data = std::fs::read("test/sample_exif.jxl").unwrap();
+        for len in 40..70 {
+            let hint = 70 + BASIC_INFO_SIZE_HINT;
+            assert_eq!(
+                basic_info(&data[..len], false),
+                Err(HeaderError::NeedMoreInput(hint))
+            );
+            assert_eq!(libjxl_size_hint(&data[..len]), hint, "{len}");
+        }
+    }
+
+    /// Writer of header bits, from the least significant bit of each byte
+    #[derive(Default, Clone)]
+    struct BitWriter {
+        data: Vec<u8>,
+        position: usize,
+    }
+
+    impl BitWriter {
+        fn bits(&mut self, n: u32, value: u32) -> &mut Self {
+            for i in 0..n {
+                if self.position == self.data.len() * 8 {
+                    self.data.push(0);
+                }
+                let bit = u8::from(value >> i & 1 == 1);
+                *self.data.last_mut().unwrap() |= bit << (self.position % 8);
+                self.position += 1;
+            }
+            self
+        }
+    }
+
+    #[test]
+    fn test_fields() {
+        let mut writer = BitWriter::default();
+        writer
+            .bits(16, 0x0aff)
+            // Small 8x16 size header
+            .bits(1, 1)
+            .bits(5, 1)
+            .bits(3, 0)
+            .bits(5, 0)
+            // Not all default, extra fields, Rotate90Cw
+            .bits(1, 0)
+            .bits(1, 1)
+            .bits(3, 5)
+            // No intrinsic size, preview or animation
+            .bits(3, 0)
+            // 16-bit float
+            .bits(1, 1)
+            .bits(2, 1)
+            .bits(4, 4)
+            .bits(1, 1)
+            // One extra channel
+            .bits(2, 1);
+        let mut unknown_channel = writer.clone();
+        let gray = |writer: &mut BitWriter| {
+            // Not XYB, gray
+            writer
+                .bits(1, 0)
+                .bits(1, 0)
+                .bits(1, 0)
+                .bits(2, 1)
+                .bits(2, 1)
+                .bits(1, 0)
+                .bits(2, 2)
+                .bits(4, 13 - 2)
+                .bits(2, 0);
+        };
+        // Default, alpha
+        writer.bits(1, 1);
+        gray(&mut writer);
+        let mut data = writer.data.clone();
+
+        // Until the tone mapping
+        assert_eq!(
+            basic_info(&data, false),
+            Err(HeaderError::NeedMoreInput(INITIAL_SIZE_HINT))
+        );
+        writer.bits(1, 1);
+        data = writer.data.clone();
+
+        let info = basic_info(&data, false).unwrap();
+        assert_eq!((info.xsize, info.ysize), (16, 8));
+        assert_eq!((info.intrinsic_xsize, info.intrinsic_ysize), (16, 8));
+        assert_eq!(info.orientation, JxlOrientation::Identity);
+        assert_eq!(
+            (info.bits_per_sample, info.exponent_bits_per_sample),
+            (16, 5)
+        );
+        assert_eq!((info.num_color_channels, info.num_extra_channels), (1, 1));
+        assert_eq!(info.alpha_bits, 8);
+        assert!(bool::from(info.uses_original_profile));
+        assert!((info.intensity_target - 255.0).abs() < f32::EPSILON);
+
+        let info = basic_info(&data, true).unwrap();
+        assert_eq!((info.xsize, info.ysize), (8, 16));
+        assert_eq!(info.orientation, JxlOrientation::Rotate90Cw);
+
+        // Not default tone mapping, with an infinite intensity target
+        let position = writer.position - 1;
+        writer.data.truncate(position / 8 + 1);
+        *writer.data.last_mut().unwrap() &= (1 << (position % 8)) - 1;
+        writer.position = position;
+        writer.bits(1, 0).bits(16, 0x7c00);
+        assert_eq!(
+            basic_info(&writer.data, false),
+            Err(HeaderError::InvalidField("infinite or NaN float"))
+        );
+
+        // Not default, of unknown type, 8-bit, without dimension shift or name
+        unknown_channel
+            .bits(1, 0)
+            .bits(2, 2)
+            .bits(4, 15 - 2)
+            .bits(1, 0)
+            .bits(2, 0)
+            .bits(2, 0)
+            .bits(2, 0);
+        gray(&mut unknown_channel);
+        unknown_channel.bits(1, 1);
+        let info = basic_info(&unknown_channel.data, false).unwrap();
+        assert_eq!(info.num_extra_channels, 1);
+        assert_eq!(info.alpha_bits, 0);
+    }
+}
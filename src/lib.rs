@@ -21,6 +21,21 @@ pub mod bindings;
 
 pub use bindings::*;
 
+pub mod memory_manager;
+
+pub mod container;
+
+pub mod header;
+
+#[cfg(feature = "dynamic-loading")]
+pub mod dynamic;
+
+#[cfg(feature = "safe")]
+pub mod safe;
+
+#[cfg(feature = "rayon")]
+pub mod rayon_runner;
+
 macro_rules! trait_impl {
     ($x:ty, [$($struct_:ident ),*]) => {
         $(
@@ -29,19 +44,34 @@ macro_rules! trait_impl {
     };
 }
 
-trait_impl!(NewUninit, [JxlBasicInfo, JxlPixelFormat, JxlColorEncoding]);
+trait_impl!(
+    NewUninit,
+    [
+        JxlBasicInfo,
+        JxlPixelFormat,
+        JxlColorEncoding,
+        JxlExtraChannelInfo,
+        JxlFrameHeader,
+        JxlBlendInfo
+    ]
+);
 
 /// Convenient function to just return a block of memory.
 /// You need to assign `basic_info.assume_init()` to use as a Rust struct after passing as a pointer.
+///
+/// Only call `assume_init` once libjxl has filled the struct. For the encoder, prefer the sound
+/// constructors such as [`JxlBasicInfo::default_for_encoder`], [`JxlColorEncoding::srgb`] and
+/// [`JxlPixelFormat::new`].
 /// # Examples:
 /// ```
 /// # use jpegxl_sys::*;
 /// # unsafe {
 /// # let decoder = JxlDecoderCreate(std::ptr::null());
 /// let mut basic_info = JxlBasicInfo::new_uninit();
-/// JxlDecoderGetBasicInfo(decoder, basic_info.as_mut_ptr());
-/// let basic_info = basic_info.assume_init();
+/// if JxlDecoderGetBasicInfo(decoder, basic_info.as_mut_ptr()) == JxlDecoderStatus::Success {
+///     let basic_info = basic_info.assume_init();
 /// }
+/// # }
 /// ```
 pub trait NewUninit {
     #[inline]
@@ -85,13 +115,158 @@ mod test {
     #[test]
     fn test_bindings_version() {
         unsafe {
-            assert_eq!(JxlDecoderVersion(), 6001);
-            assert_eq!(JxlEncoderVersion(), 6001);
+            #[cfg(not(jxl_0_8))]
+            {
+                assert_eq!(JxlDecoderVersion() / 1000, 7);
+                assert_eq!(JxlEncoderVersion() / 1000, 7);
+            }
+            #[cfg(jxl_0_8)]
+            {
+                assert!(JxlDecoderVersion() >= 8000);
+                assert!(JxlEncoderVersion() >= 8000);
+            }
         }
     }
 
+    #[test]
+    fn test_bindings_events() {
+        let events = jxl_dec_events!(JxlDecoderStatus::BasicInfo, JxlDecoderStatus::FullImage);
+        assert_eq!(
+            events,
+            JxlDecoderEvents::BASIC_INFO | JxlDecoderEvents::FULL_IMAGE
+        );
+        assert_eq!(events.bits(), 0x1040);
+        assert!(events.contains(JxlDecoderEvents::FULL_IMAGE));
+        assert!(!events.contains(JxlDecoderEvents::FRAME));
+        assert!(jxl_dec_events!().is_empty());
+
+        let mut events = JxlDecoderEvents::empty();
+        events |= JxlDecoderEvents::from_status(JxlDecoderStatus::Box);
+        assert_eq!(events, JxlDecoderEvents::BOX);
+
+        assert_eq!(
+            JxlDecoderEvents::from_bits(0x1040),
+            Some(JxlDecoderEvents::BASIC_INFO | JxlDecoderEvents::FULL_IMAGE)
+        );
+        assert_eq!(
+            JxlDecoderEvents::from_bits(JxlDecoderStatus::NeedMoreInput.0),
+            None
+        );
+        assert_eq!(JxlDecoderEvents::from_bits(0x10000), None);
+        assert_eq!(
+            JxlDecoderEvents::from_bits(JxlDecoderEvents::all().bits()),
+            Some(JxlDecoderEvents::all())
+        );
+        assert_eq!(JxlDecoderEvents::from_bits_retain(0x10000).bits(), 0x10000);
+
+        // The DC events were removed in 0.8
+        #[cfg(not(jxl_0_8))]
+        assert_eq!(
+            JxlDecoderEvents::from_bits(0x880),
+            Some(JxlDecoderEvents::EXTENSIONS | JxlDecoderEvents::DC_IMAGE)
+        );
+        #[cfg(jxl_0_8)]
+        assert_eq!(JxlDecoderEvents::from_bits(0x800), None);
+
+        // Statuses only known at runtime
+        let statuses = [JxlDecoderStatus::BasicInfo, JxlDecoderStatus::FullImage];
+        assert_eq!(
+            jxl_dec_events!(statuses[0], statuses[1]),
+            JxlDecoderEvents::BASIC_INFO | JxlDecoderEvents::FULL_IMAGE
+        );
+    }
+
+    #[test]
+    #[should_panic = "not an event"]
+    fn test_bindings_events_runtime_status() {
+        let status = JxlDecoderStatus::NeedMoreInput;
+        let _ = jxl_dec_events!(status);
+    }
+
+    #[test]
+    #[should_panic = "not an event"]
+    fn test_bindings_events_non_event() {
+        let status = JxlDecoderStatus::NeedImageOutBuffer;
+        let _ = JxlDecoderEvents::from_status(status);
+    }
+
+    #[test]
+    fn test_bindings_unknown_values() {
+        // Values a newer libjxl could return
+        let status = JxlDecoderStatus(0x10000);
+        assert_eq!(DecoderStatus::try_from(status), Err(status));
+        assert_eq!(format!("{status:?}"), "JxlDecoderStatus(65536)");
+        assert_eq!(
+            DecoderStatus::try_from(JxlDecoderStatus::FullImage),
+            Ok(DecoderStatus::FullImage)
+        );
+        assert_eq!(format!("{:?}", JxlDecoderStatus::FullImage), "FullImage");
+        assert_eq!(
+            JxlDecoderStatus::from(DecoderStatus::Box),
+            JxlDecoderStatus::Box
+        );
+
+        assert_eq!(
+            EncoderStatus::try_from(JxlEncoderStatus(-1)),
+            Err(JxlEncoderStatus(-1))
+        );
+        assert_eq!(Signature::try_from(JxlSignature(4)), Err(JxlSignature(4)));
+
+        // Fields written by libjxl
+        let blend_info: JxlBlendInfo = unsafe { std::mem::transmute([42_i32, 0, 0, 2]) };
+        assert_eq!(
+            BlendMode::try_from(blend_info.blendmode),
+            Err(JxlBlendMode(42))
+        );
+        assert!(bool::from(blend_info.clamp));
+        assert_eq!(format!("{:?}", blend_info.clamp), "JxlBool(2)");
+
+        let mut encoding = JxlColorEncoding::srgb(false);
+        encoding.transfer_function = JxlTransferFunction(100);
+        assert_eq!(encoding.color_space, JxlColorSpace::Rgb);
+        assert_eq!(
+            TransferFunction::try_from(encoding.transfer_function),
+            Err(JxlTransferFunction(100))
+        );
+        assert!(format!("{encoding:?}").contains("JxlTransferFunction(100)"));
+    }
+
+    #[test]
+    fn test_bindings_constructors() {
+        let basic_info = JxlBasicInfo::default_for_encoder();
+        assert_eq!(basic_info.bits_per_sample, 8);
+        assert_eq!(basic_info.num_color_channels, 3);
+        assert_eq!(basic_info.orientation, JxlOrientation::Identity);
+
+        let info = JxlExtraChannelInfo::default_for_encoder(JxlExtraChannelType::Alpha);
+        assert_eq!(info.type_, JxlExtraChannelType::Alpha);
+
+        let header = JxlFrameHeader::default_for_encoder();
+        assert_eq!(
+            header.layer_info.blend_info,
+            JxlBlendInfo::default_for_encoder()
+        );
+        assert_eq!(
+            header.layer_info.blend_info.blendmode,
+            JxlBlendMode::Replace
+        );
+
+        let srgb = JxlColorEncoding::srgb(false);
+        assert_eq!(srgb.color_space, JxlColorSpace::Rgb);
+        assert_eq!(srgb.white_point, JxlWhitePoint::D65);
+        assert_eq!(srgb.transfer_function, JxlTransferFunction::SRgb);
+        let linear = JxlColorEncoding::linear_srgb(true);
+        assert_eq!(linear.color_space, JxlColorSpace::Gray);
+        assert_eq!(linear.transfer_function, JxlTransferFunction::Linear);
+
+        let format = JxlPixelFormat::new(4, JxlDataType::Uint16);
+        assert_eq!(format.endianness, JxlEndianness::Native);
+        assert_eq!(format.align, 0);
+        assert_eq!(JxlPixelFormat::default().num_channels, 0);
+    }
+
     unsafe fn decode(decoder: *mut JxlDecoder, sample: &[u8]) {
-        use JxlDecoderStatus::{
+        use DecoderStatus::{
             BasicInfo, Error, FullImage, NeedImageOutBuffer, NeedMoreInput, Success,
         };
 
@@ -116,7 +291,6 @@ mod test {
             align: 0,
         };
 
-        let mut basic_info = JxlBasicInfo::new_uninit().assume_init();
         let mut buffer: Vec<f32> = Vec::new();
         let mut x_size = 0;
         let mut y_size = 0;
@@ -127,7 +301,7 @@ mod test {
         loop {
             status = JxlDecoderProcessInput(decoder);
 
-            match status {
+            match DecoderStatus::try_from(status).unwrap() {
                 Error => panic!("Decoder error!"),
                 NeedMoreInput => {
                     panic!("Error, already provided all input")
@@ -135,8 +309,10 @@ mod test {
 
                 // Get the basic info
                 BasicInfo => {
-                    status = JxlDecoderGetBasicInfo(decoder, &mut basic_info);
+                    let mut basic_info = JxlBasicInfo::new_uninit();
+                    status = JxlDecoderGetBasicInfo(decoder, basic_info.as_mut_ptr());
                     jxl_dec_assert!(status, "BasicInfo");
+                    let basic_info = basic_info.assume_init();
                     x_size = basic_info.xsize;
                     y_size = basic_info.ysize;
                     assert_eq!(basic_info.xsize, 40, "Width");
@@ -210,7 +386,7 @@ mod test {
     #[test]
     #[cfg(feature = "threads")]
     fn test_bindings_resizable() {
-        use JxlDecoderStatus::{
+        use DecoderStatus::{
             BasicInfo, Error, FullImage, NeedImageOutBuffer, NeedMoreInput, Success,
         };
 
@@ -253,7 +429,6 @@ mod test {
                 align: 0,
             };
 
-            let mut basic_info = JxlBasicInfo::new_uninit().assume_init();
             let mut buffer: Vec<f32> = Vec::new();
             let mut x_size = 0;
             let mut y_size = 0;
@@ -264,7 +439,7 @@ mod test {
             loop {
                 status = JxlDecoderProcessInput(dec);
 
-                match status {
+                match DecoderStatus::try_from(status).unwrap() {
                     Error => panic!("Decoder error!"),
                     NeedMoreInput => {
                         panic!("Error, already provided all input")
@@ -272,8 +447,10 @@ mod test {
 
                     // Get the basic info
                     BasicInfo => {
-                        status = JxlDecoderGetBasicInfo(dec, &mut basic_info);
+                        let mut basic_info = JxlBasicInfo::new_uninit();
+                        status = JxlDecoderGetBasicInfo(dec, basic_info.as_mut_ptr());
                         jxl_dec_assert!(status, "BasicInfo");
+                        let basic_info = basic_info.assume_init();
                         x_size = basic_info.xsize;
                         y_size = basic_info.ysize;
 
@@ -317,7 +494,12 @@ mod test {
         }
     }
 
-    fn encode(pixels: &[u8], x_size: u32, ysize: u32) -> Vec<u8> {
+    fn encode(
+        pixels: &[u8],
+        x_size: u32,
+        ysize: u32,
+        settings: &[(JxlEncoderFrameSettingId, i64)],
+    ) -> Vec<u8> {
         unsafe {
             let enc = JxlEncoderCreate(std::ptr::null());
 
@@ -329,8 +511,7 @@ mod test {
             let mut status = JxlEncoderSetParallelRunner(enc, JxlThreadParallelRunner, runner);
             jxl_enc_assert!(status, "Set Parallel Runner");
 
-            let mut basic_info = JxlBasicInfo::new_uninit().assume_init();
-            JxlEncoderInitBasicInfo(&mut basic_info);
+            let mut basic_info = JxlBasicInfo::default_for_encoder();
             basic_info.xsize = x_size;
             basic_info.ysize = ysize;
 
@@ -343,39 +524,25 @@ mod test {
                 endianness: JxlEndianness::Native,
                 align: 0,
             };
-            let mut color_encoding = JxlColorEncoding::new_uninit().assume_init();
-            JxlColorEncodingSetToSRGB(&mut color_encoding, false);
+            let color_encoding = JxlColorEncoding::srgb(false);
             status = JxlEncoderSetColorEncoding(enc, &color_encoding);
             jxl_enc_assert!(status, "Set Color Encoding");
 
+            let frame_settings = JxlEncoderFrameSettingsCreate(enc, std::ptr::null());
+            for &(option, value) in settings {
+                status = JxlEncoderFrameSettingsSetOption(frame_settings, option, value);
+                jxl_enc_assert!(status, "Set Frame Setting");
+            }
+
             status = JxlEncoderAddImageFrame(
-                JxlEncoderOptionsCreate(enc, std::ptr::null()),
+                frame_settings,
                 &pixel_format,
                 pixels.as_ptr() as *mut std::ffi::c_void,
                 pixels.len(),
             );
             jxl_enc_assert!(status, "Add Image Frame");
 
-            let chunk_size = 1024 * 512; // 512 KB is a good initial value
-            let mut buffer = vec![0u8; chunk_size];
-            let mut next_out = buffer.as_mut_ptr();
-            let mut avail_out = chunk_size;
-
-            loop {
-                status =
-                    JxlEncoderProcessOutput(enc, std::ptr::addr_of_mut!(next_out), &mut avail_out);
-
-                if status != JxlEncoderStatus::NeedMoreOutput {
-                    break;
-                }
-
-                let offset = next_out as usize - buffer.as_ptr() as usize;
-                buffer.resize(buffer.len() * 2, 0);
-                next_out = buffer.as_mut_ptr().add(offset);
-                avail_out = buffer.len() - offset;
-            }
-            buffer.truncate(next_out as usize - buffer.as_ptr() as usize);
-            jxl_enc_assert!(status, "Encoding");
+            let buffer = process_output(enc);
 
             JxlEncoderDestroy(enc);
             JxlThreadParallelRunnerDestroy(runner);
@@ -384,6 +551,31 @@ mod test {
         }
     }
 
+    unsafe fn process_output(enc: *mut JxlEncoder) -> Vec<u8> {
+        let chunk_size = 1024 * 512; // 512 KB is a good initial value
+        let mut buffer = vec![0u8; chunk_size];
+        let mut next_out = buffer.as_mut_ptr();
+        let mut avail_out = chunk_size;
+
+        let mut status;
+        loop {
+            status = JxlEncoderProcessOutput(enc, std::ptr::addr_of_mut!(next_out), &mut avail_out);
+
+            if status != JxlEncoderStatus::NeedMoreOutput {
+                break;
+            }
+
+            let offset = next_out as usize - buffer.as_ptr() as usize;
+            buffer.resize(buffer.len() * 2, 0);
+            next_out = buffer.as_mut_ptr().add(offset);
+            avail_out = buffer.len() - offset;
+        }
+        buffer.truncate(next_out as usize - buffer.as_ptr() as usize);
+        jxl_enc_assert!(status, "Encoding");
+
+        buffer
+    }
+
     #[test]
     fn test_bindings_encoding() {
         || -> Result<(), ImageError> {
@@ -394,6 +586,7 @@ mod test {
                 image_buffer.as_raw(),
                 image_buffer.width(),
                 image_buffer.height(),
+                &[],
             );
 
             unsafe {
@@ -418,4 +611,808 @@ mod test {
         }()
         .unwrap();
     }
+
+    #[test]
+    fn test_bindings_frame_settings() {
+        use JxlEncoderFrameSettingId::{
+            DecodingSpeed, Effort, Epf, Gaborish, GroupOrder, Modular, Patches, PhotonNoise,
+            Responsive,
+        };
+
+        || -> Result<(), ImageError> {
+            let img = ImageReader::open("test/sample.png")?.decode()?;
+            let image_buffer = img.into_rgb8();
+
+            let settings: &[&[(JxlEncoderFrameSettingId, i64)]] = &[
+                &[(Effort, 3), (DecodingSpeed, 2)],
+                &[(Modular, 1), (Responsive, 1), (Patches, 0)],
+                &[(Modular, 0), (Epf, 1), (Gaborish, 0), (GroupOrder, 1)],
+            ];
+
+            for settings in settings {
+                let output = encode(
+                    image_buffer.as_raw(),
+                    image_buffer.width(),
+                    image_buffer.height(),
+                    settings,
+                );
+
+                unsafe {
+                    let dec = JxlDecoderCreate(ptr::null());
+                    assert!(!dec.is_null());
+                    decode(dec, &output);
+                    JxlDecoderDestroy(dec);
+                }
+            }
+
+            unsafe {
+                let enc = JxlEncoderCreate(ptr::null());
+                let frame_settings = JxlEncoderFrameSettingsCreate(enc, ptr::null());
+
+                let status =
+                    JxlEncoderFrameSettingsSetFloatOption(frame_settings, PhotonNoise, 3200.0);
+                jxl_enc_assert!(status, "Set Photon Noise");
+
+                let status = JxlEncoderFrameSettingsSetOption(frame_settings, Effort, 42);
+                assert_eq!(status, JxlEncoderStatus::Error, "Effort out of range");
+
+                JxlEncoderDestroy(enc);
+            }
+
+            Ok(())
+        }()
+        .unwrap();
+    }
+
+    #[test]
+    #[cfg(not(jxl_0_8))]
+    #[allow(deprecated)]
+    fn test_bindings_options() {
+        unsafe {
+            let enc = JxlEncoderCreate(ptr::null());
+            let options = JxlEncoderOptionsCreate(enc, ptr::null());
+            assert!(!options.is_null());
+
+            let status = JxlEncoderOptionsSetDistance(options, 1.5);
+            jxl_enc_assert!(status, "Set Distance");
+            let status = JxlEncoderOptionsSetEffort(options, 3);
+            jxl_enc_assert!(status, "Set Effort");
+            let status = JxlEncoderOptionsSetDecodingSpeed(options, 2);
+            jxl_enc_assert!(status, "Set Decoding Speed");
+            let status = JxlEncoderOptionsSetLossless(options, false);
+            jxl_enc_assert!(status, "Set Lossless");
+
+            JxlEncoderDestroy(enc);
+        }
+    }
+
+    #[test]
+    #[cfg(jxl_0_8)]
+    fn test_bindings_bit_depth() {
+        unsafe {
+            let enc = JxlEncoderCreate(ptr::null());
+            let frame_settings = JxlEncoderFrameSettingsCreate(enc, ptr::null());
+
+            let bit_depth = JxlBitDepth {
+                type_: JxlBitDepthType::FromCodestream,
+                bits_per_sample: 0,
+                exponent_bits_per_sample: 0,
+            };
+            let status = JxlEncoderSetFrameBitDepth(frame_settings, ptr::addr_of!(bit_depth));
+            jxl_enc_assert!(status, "Set Frame Bit Depth");
+
+            JxlEncoderDestroy(enc);
+        }
+    }
+
+    const ALPHA_NAME: &str = "alpha";
+
+    unsafe fn encode_with_alpha_plane(
+        pixels: &[u8],
+        alpha: &[u8],
+        x_size: u32,
+        y_size: u32,
+    ) -> Vec<u8> {
+        let color_format = JxlPixelFormat {
+            num_channels: 3,
+            data_type: JxlDataType::Uint8,
+            endianness: JxlEndianness::Native,
+            align: 0,
+        };
+        let alpha_format = JxlPixelFormat {
+            num_channels: 1,
+            ..color_format.clone()
+        };
+
+        let enc = JxlEncoderCreate(ptr::null());
+        assert!(!enc.is_null());
+
+        let mut basic_info = JxlBasicInfo::default_for_encoder();
+        basic_info.xsize = x_size;
+        basic_info.ysize = y_size;
+        basic_info.uses_original_profile = JxlBool::True;
+        basic_info.num_extra_channels = 1;
+        basic_info.alpha_bits = 8;
+
+        let mut status = JxlEncoderSetBasicInfo(enc, &basic_info);
+        jxl_enc_assert!(status, "Set Basic Info");
+
+        let color_encoding = JxlColorEncoding::srgb(false);
+        status = JxlEncoderSetColorEncoding(enc, &color_encoding);
+        jxl_enc_assert!(status, "Set Color Encoding");
+
+        let mut extra_channel_info =
+            JxlExtraChannelInfo::default_for_encoder(JxlExtraChannelType::Alpha);
+        extra_channel_info.bits_per_sample = 8;
+        status = JxlEncoderSetExtraChannelInfo(enc, 0, &extra_channel_info);
+        jxl_enc_assert!(status, "Set Extra Channel Info");
+
+        status =
+            JxlEncoderSetExtraChannelName(enc, 0, ALPHA_NAME.as_ptr().cast(), ALPHA_NAME.len());
+        jxl_enc_assert!(status, "Set Extra Channel Name");
+
+        let frame_settings = JxlEncoderFrameSettingsCreate(enc, ptr::null());
+        status = JxlEncoderSetFrameLossless(frame_settings, true);
+        jxl_enc_assert!(status, "Set Lossless");
+
+        status = JxlEncoderAddImageFrame(
+            frame_settings,
+            &color_format,
+            pixels.as_ptr().cast(),
+            pixels.len(),
+        );
+        jxl_enc_assert!(status, "Add Image Frame");
+
+        // The extra channel buffer is attached to the last added frame
+        status = JxlEncoderSetExtraChannelBuffer(
+            frame_settings,
+            &alpha_format,
+            alpha.as_ptr().cast(),
+            alpha.len(),
+            0,
+        );
+        jxl_enc_assert!(status, "Set Extra Channel Buffer");
+
+        JxlEncoderCloseInput(enc);
+        let output = process_output(enc);
+        JxlEncoderDestroy(enc);
+
+        output
+    }
+
+    #[test]
+    fn test_bindings_extra_channels() {
+        use DecoderStatus::{BasicInfo, Error, FullImage, NeedImageOutBuffer, Success};
+
+        let (x_size, y_size) = (40, 50);
+        let num_pixels = (x_size * y_size) as usize;
+        let pixels = vec![128u8; num_pixels * 3];
+        #[allow(clippy::cast_possible_truncation)]
+        let alpha: Vec<u8> = (0..num_pixels).map(|i| i as u8).collect();
+
+        let color_format = JxlPixelFormat {
+            num_channels: 3,
+            data_type: JxlDataType::Uint8,
+            endianness: JxlEndianness::Native,
+            align: 0,
+        };
+        let alpha_format = JxlPixelFormat {
+            num_channels: 1,
+            ..color_format.clone()
+        };
+
+        unsafe {
+            let output = encode_with_alpha_plane(&pixels, &alpha, x_size, y_size);
+
+            let dec = JxlDecoderCreate(ptr::null());
+            assert!(!dec.is_null());
+
+            let mut status = JxlDecoderSubscribeEvents(
+                dec,
+                jxl_dec_events!(JxlDecoderStatus::BasicInfo, JxlDecoderStatus::FullImage),
+            );
+            jxl_dec_assert!(status, "Subscribe Events");
+
+            status = JxlDecoderSetInput(dec, output.as_ptr(), output.len());
+            jxl_dec_assert!(status, "Set input");
+
+            let mut color_buffer = Vec::<u8>::new();
+            let mut alpha_buffer = Vec::<u8>::new();
+
+            loop {
+                status = JxlDecoderProcessInput(dec);
+
+                match DecoderStatus::try_from(status).unwrap() {
+                    BasicInfo => {
+                        let mut basic_info = JxlBasicInfo::new_uninit();
+                        status = JxlDecoderGetBasicInfo(dec, basic_info.as_mut_ptr());
+                        jxl_dec_assert!(status, "BasicInfo");
+                        let basic_info = basic_info.assume_init();
+                        assert_eq!(basic_info.num_extra_channels, 1);
+                        assert_eq!(basic_info.alpha_bits, 8);
+
+                        let mut extra_channel_info = JxlExtraChannelInfo::new_uninit();
+                        status =
+                            JxlDecoderGetExtraChannelInfo(dec, 0, extra_channel_info.as_mut_ptr());
+                        jxl_dec_assert!(status, "ExtraChannelInfo");
+                        let extra_channel_info = extra_channel_info.assume_init();
+                        assert_eq!(extra_channel_info.type_, JxlExtraChannelType::Alpha);
+                        assert_eq!(extra_channel_info.name_length as usize, ALPHA_NAME.len());
+
+                        let mut name = vec![0u8; ALPHA_NAME.len() + 1];
+                        status = JxlDecoderGetExtraChannelName(
+                            dec,
+                            0,
+                            name.as_mut_ptr().cast(),
+                            name.len(),
+                        );
+                        jxl_dec_assert!(status, "ExtraChannelName");
+                        assert_eq!(&name[..ALPHA_NAME.len()], ALPHA_NAME.as_bytes());
+                    }
+
+                    NeedImageOutBuffer => {
+                        let mut size = 0;
+                        status = JxlDecoderImageOutBufferSize(dec, &color_format, &mut size);
+                        jxl_dec_assert!(status, "BufferSize");
+                        color_buffer.resize(size, 0);
+                        status = JxlDecoderSetImageOutBuffer(
+                            dec,
+                            &color_format,
+                            color_buffer.as_mut_ptr().cast(),
+                            size,
+                        );
+                        jxl_dec_assert!(status, "SetBuffer");
+
+                        status = JxlDecoderExtraChannelBufferSize(dec, &alpha_format, &mut size, 0);
+                        jxl_dec_assert!(status, "ExtraChannelBufferSize");
+                        alpha_buffer.resize(size, 0);
+                        status = JxlDecoderSetExtraChannelBuffer(
+                            dec,
+                            &alpha_format,
+                            alpha_buffer.as_mut_ptr().cast(),
+                            size,
+                            0,
+                        );
+                        jxl_dec_assert!(status, "SetExtraChannelBuffer");
+                    }
+
+                    FullImage => (),
+                    Success => break,
+                    Error => panic!("Decoder error!"),
+                    _ => panic!("Unknown decoder status: {:#?}", status),
+                }
+            }
+
+            JxlDecoderDestroy(dec);
+
+            assert_eq!(color_buffer, pixels);
+            assert_eq!(alpha_buffer, alpha);
+        }
+    }
+
+    /// Contents of the `Exif` box in `test/sample_exif.jxl`: a zero TIFF header offset followed by
+    /// a little-endian TIFF with a single orientation entry.
+    const SAMPLE_EXIF: &[u8] = &[
+        0, 0, 0, 0, b'I', b'I', 42, 0, 8, 0, 0, 0, 1, 0, 0x12, 0x01, 3, 0, 1, 0, 0, 0, 1, 0, 0, 0,
+        0, 0, 0, 0,
+    ];
+
+    unsafe fn read_box(dec: *mut JxlDecoder, box_type: &[u8; 4]) -> Option<Vec<u8>> {
+        use DecoderStatus::{Box, Error, NeedMoreInput, Success};
+
+        #[allow(clippy::cast_possible_wrap)]
+        let box_type: JxlBoxType = box_type.map(|c| c as _);
+
+        let mut status = JxlDecoderSubscribeEvents(dec, jxl_dec_events!(JxlDecoderStatus::Box));
+        jxl_dec_assert!(status, "Subscribe Events");
+        status = JxlDecoderSetDecompressBoxes(dec, true);
+        jxl_dec_assert!(status, "Set Decompress Boxes");
+
+        let mut contents: Option<Vec<u8>> = None;
+
+        loop {
+            status = JxlDecoderProcessInput(dec);
+
+            // Any event after setting the box buffer means the box is finished,
+            // unless it needs more space
+            if let Some(mut buffer) = contents.take() {
+                let filled = buffer.len() - JxlDecoderReleaseBoxBuffer(dec);
+                if status != JxlDecoderStatus::BoxNeedMoreOutput {
+                    buffer.truncate(filled);
+                    return Some(buffer);
+                }
+
+                buffer.resize(buffer.len() * 2, 0);
+                status = JxlDecoderSetBoxBuffer(
+                    dec,
+                    buffer.as_mut_ptr().add(filled),
+                    buffer.len() - filled,
+                );
+                jxl_dec_assert!(status, "Set Box Buffer");
+                contents = Some(buffer);
+                continue;
+            }
+
+            match DecoderStatus::try_from(status).unwrap() {
+                Box => {
+                    let mut current_type: JxlBoxType = [0; 4];
+                    status = JxlDecoderGetBoxType(dec, &mut current_type, true);
+                    jxl_dec_assert!(status, "Get Box Type");
+                    if current_type != box_type {
+                        continue;
+                    }
+
+                    let mut size = 0;
+                    status = JxlDecoderGetBoxSizeRaw(dec, &mut size);
+                    jxl_dec_assert!(status, "Get Box Size");
+
+                    // The raw size includes the box header, contents may grow when decompressed
+                    let mut buffer = vec![0u8; usize::try_from(size).unwrap().max(64)];
+                    status = JxlDecoderSetBoxBuffer(dec, buffer.as_mut_ptr(), buffer.len());
+                    jxl_dec_assert!(status, "Set Box Buffer");
+                    contents = Some(buffer);
+                }
+                Success => return None,
+                NeedMoreInput => panic!("Error, already provided all input"),
+                Error => panic!("Decoder error!"),
+                _ => panic!("Unknown decoder status: {:#?}", status),
+            }
+        }
+    }
+
+    #[test]
+    fn test_bindings_box() {
+        let sample = std::fs::read("test/sample_exif.jxl").unwrap();
+
+        unsafe {
+            let signature = JxlSignatureCheck(sample.as_ptr(), sample.len());
+            assert_eq!(signature, JxlSignature::Container, "Signature");
+
+            let dec = JxlDecoderCreate(ptr::null());
+            assert!(!dec.is_null());
+
+            let status = JxlDecoderSetInput(dec, sample.as_ptr(), sample.len());
+            jxl_dec_assert!(status, "Set input");
+
+            let exif = read_box(dec, b"Exif");
+            assert_eq!(exif.as_deref(), Some(SAMPLE_EXIF));
+
+            JxlDecoderDestroy(dec);
+        }
+    }
+
+    unsafe fn encode_with_box(
+        pixels: &[u8],
+        x_size: u32,
+        y_size: u32,
+        box_type: &[u8; 4],
+        contents: &[u8],
+        compress_box: bool,
+    ) -> Vec<u8> {
+        let enc = JxlEncoderCreate(ptr::null());
+        assert!(!enc.is_null());
+
+        let mut status = JxlEncoderUseBoxes(enc);
+        jxl_enc_assert!(status, "Use Boxes");
+
+        let mut basic_info = JxlBasicInfo::default_for_encoder();
+        basic_info.xsize = x_size;
+        basic_info.ysize = y_size;
+        status = JxlEncoderSetBasicInfo(enc, &basic_info);
+        jxl_enc_assert!(status, "Set Basic Info");
+
+        let color_encoding = JxlColorEncoding::srgb(false);
+        status = JxlEncoderSetColorEncoding(enc, &color_encoding);
+        jxl_enc_assert!(status, "Set Color Encoding");
+
+        #[allow(clippy::cast_possible_wrap)]
+        let box_type: JxlBoxType = box_type.map(|c| c as _);
+        status = JxlEncoderAddBox(
+            enc,
+            &box_type,
+            contents.as_ptr(),
+            contents.len(),
+            compress_box,
+        );
+        jxl_enc_assert!(status, "Add Box");
+        JxlEncoderCloseBoxes(enc);
+
+        let pixel_format = JxlPixelFormat {
+            num_channels: 3,
+            data_type: JxlDataType::Uint8,
+            endianness: JxlEndianness::Native,
+            align: 0,
+        };
+        status = JxlEncoderAddImageFrame(
+            JxlEncoderFrameSettingsCreate(enc, ptr::null()),
+            &pixel_format,
+            pixels.as_ptr().cast(),
+            pixels.len(),
+        );
+        jxl_enc_assert!(status, "Add Image Frame");
+        JxlEncoderCloseFrames(enc);
+
+        let output = process_output(enc);
+        JxlEncoderDestroy(enc);
+
+        output
+    }
+
+    #[test]
+    fn test_bindings_add_box() {
+        || -> Result<(), ImageError> {
+            let img = ImageReader::open("test/sample.png")?.decode()?;
+            let image_buffer = img.into_rgb8();
+
+            for compress_box in [false, true] {
+                unsafe {
+                    let output = encode_with_box(
+                        image_buffer.as_raw(),
+                        image_buffer.width(),
+                        image_buffer.height(),
+                        b"Exif",
+                        SAMPLE_EXIF,
+                        compress_box,
+                    );
+
+                    let signature = JxlSignatureCheck(output.as_ptr(), output.len());
+                    assert_eq!(signature, JxlSignature::Container, "Signature");
+
+                    let dec = JxlDecoderCreate(ptr::null());
+                    assert!(!dec.is_null());
+
+                    let status = JxlDecoderSetInput(dec, output.as_ptr(), output.len());
+                    jxl_dec_assert!(status, "Set input");
+
+                    let exif = read_box(dec, b"Exif");
+                    assert_eq!(exif.as_deref(), Some(SAMPLE_EXIF), "{compress_box}");
+
+                    JxlDecoderDestroy(dec);
+                }
+            }
+
+            Ok(())
+        }()
+        .unwrap();
+    }
+
+    unsafe fn encode_frames(
+        basic_info: &JxlBasicInfo,
+        frames: &[(JxlFrameHeader, &str, Vec<u8>)],
+    ) -> Vec<u8> {
+        let enc = JxlEncoderCreate(ptr::null());
+        assert!(!enc.is_null());
+
+        let mut status = JxlEncoderSetBasicInfo(enc, basic_info);
+        jxl_enc_assert!(status, "Set Basic Info");
+
+        let color_encoding = JxlColorEncoding::srgb(false);
+        status = JxlEncoderSetColorEncoding(enc, &color_encoding);
+        jxl_enc_assert!(status, "Set Color Encoding");
+
+        let pixel_format = JxlPixelFormat {
+            num_channels: 3,
+            data_type: JxlDataType::Uint8,
+            endianness: JxlEndianness::Native,
+            align: 0,
+        };
+
+        for (header, name, pixels) in frames {
+            let frame_settings = JxlEncoderFrameSettingsCreate(enc, ptr::null());
+
+            status = JxlEncoderSetFrameHeader(frame_settings, header);
+            jxl_enc_assert!(status, "Set Frame Header");
+
+            let name = std::ffi::CString::new(*name).unwrap();
+            status = JxlEncoderSetFrameName(frame_settings, name.as_ptr());
+            jxl_enc_assert!(status, "Set Frame Name");
+
+            status = JxlEncoderAddImageFrame(
+                frame_settings,
+                &pixel_format,
+                pixels.as_ptr().cast(),
+                pixels.len(),
+            );
+            jxl_enc_assert!(status, "Add Image Frame");
+        }
+
+        JxlEncoderCloseInput(enc);
+        let output = process_output(enc);
+        JxlEncoderDestroy(enc);
+
+        output
+    }
+
+    unsafe fn decode_frames(sample: &[u8], coalescing: bool) -> Vec<(JxlFrameHeader, String)> {
+        use DecoderStatus::{Error, Frame, FullImage, NeedImageOutBuffer, Success};
+
+        let dec = JxlDecoderCreate(ptr::null());
+        assert!(!dec.is_null());
+
+        let mut status = JxlDecoderSubscribeEvents(
+            dec,
+            jxl_dec_events!(JxlDecoderStatus::Frame, JxlDecoderStatus::FullImage),
+        );
+        jxl_dec_assert!(status, "Subscribe Events");
+
+        status = JxlDecoderSetCoalescing(dec, coalescing);
+        jxl_dec_assert!(status, "Set Coalescing");
+
+        status = JxlDecoderSetInput(dec, sample.as_ptr(), sample.len());
+        jxl_dec_assert!(status, "Set input");
+
+        let pixel_format = JxlPixelFormat {
+            num_channels: 3,
+            data_type: JxlDataType::Uint8,
+            endianness: JxlEndianness::Native,
+            align: 0,
+        };
+
+        let mut frames = Vec::new();
+        let mut buffer = Vec::<u8>::new();
+
+        loop {
+            status = JxlDecoderProcessInput(dec);
+
+            match DecoderStatus::try_from(status).unwrap() {
+                Frame => {
+                    let mut header = JxlFrameHeader::new_uninit();
+                    status = JxlDecoderGetFrameHeader(dec, header.as_mut_ptr());
+                    jxl_dec_assert!(status, "Get Frame Header");
+                    let header = header.assume_init();
+
+                    let mut name = vec![0u8; header.name_length as usize + 1];
+                    status = JxlDecoderGetFrameName(dec, name.as_mut_ptr().cast(), name.len());
+                    jxl_dec_assert!(status, "Get Frame Name");
+                    name.pop();
+
+                    frames.push((header, String::from_utf8(name).unwrap()));
+                }
+
+                NeedImageOutBuffer => {
+                    let mut size = 0;
+                    status = JxlDecoderImageOutBufferSize(dec, &pixel_format, &mut size);
+                    jxl_dec_assert!(status, "BufferSize");
+
+                    buffer.resize(size, 0);
+                    status = JxlDecoderSetImageOutBuffer(
+                        dec,
+                        &pixel_format,
+                        buffer.as_mut_ptr().cast(),
+                        size,
+                    );
+                    jxl_dec_assert!(status, "SetBuffer");
+                }
+
+                FullImage => (),
+                Success => break,
+                Error => panic!("Decoder error!"),
+                _ => panic!("Unknown decoder status: {:#?}", status),
+            }
+        }
+
+        JxlDecoderDestroy(dec);
+
+        frames
+    }
+
+    #[test]
+    fn test_bindings_animation() {
+        unsafe {
+            let mut basic_info = JxlBasicInfo::default_for_encoder();
+            basic_info.xsize = 40;
+            basic_info.ysize = 50;
+            basic_info.have_animation = JxlBool::True;
+            basic_info.animation.tps_numerator = 100;
+            basic_info.animation.tps_denominator = 1;
+
+            let frames: Vec<_> = [(10u8, "first"), (20, "second")]
+                .into_iter()
+                .map(|(duration, name)| {
+                    let mut header = JxlFrameHeader::default_for_encoder();
+                    header.duration = duration.into();
+                    (header, name, vec![duration; 40 * 50 * 3])
+                })
+                .collect();
+
+            let output = encode_frames(&basic_info, &frames);
+            let decoded = decode_frames(&output, true);
+
+            assert_eq!(decoded.len(), 2);
+            assert_eq!(decoded[0].0.duration, 10);
+            assert_eq!(decoded[0].0.is_last, JxlBool::False);
+            assert_eq!(decoded[0].1, "first");
+            assert_eq!(decoded[1].0.duration, 20);
+            assert_eq!(decoded[1].0.is_last, JxlBool::True);
+            assert_eq!(decoded[1].1, "second");
+        }
+    }
+
+    #[test]
+    fn test_bindings_layers() {
+        unsafe {
+            let mut basic_info = JxlBasicInfo::default_for_encoder();
+            basic_info.xsize = 40;
+            basic_info.ysize = 50;
+
+            let background = JxlFrameHeader::default_for_encoder();
+
+            let mut layer = background.clone();
+            layer.layer_info.have_crop = JxlBool::True;
+            layer.layer_info.crop_x0 = 10;
+            layer.layer_info.crop_y0 = 5;
+            layer.layer_info.xsize = 20;
+            layer.layer_info.ysize = 20;
+            JxlEncoderInitBlendInfo(&mut layer.layer_info.blend_info);
+            layer.layer_info.blend_info.blendmode = JxlBlendMode::Add;
+
+            let frames = [
+                (background, "background", vec![64u8; 40 * 50 * 3]),
+                (layer, "layer", vec![32u8; 20 * 20 * 3]),
+            ];
+
+            let output = encode_frames(&basic_info, &frames);
+
+            // Coalesced, only the composited image is returned
+            let decoded = decode_frames(&output, true);
+            assert_eq!(decoded.len(), 1);
+
+            let decoded = decode_frames(&output, false);
+            assert_eq!(decoded.len(), 2);
+            assert_eq!(decoded[0].1, "background");
+            assert_eq!(decoded[0].0.layer_info.have_crop, JxlBool::False);
+
+            let layer_info = &decoded[1].0.layer_info;
+            assert_eq!(decoded[1].1, "layer");
+            assert_eq!(layer_info.have_crop, JxlBool::True);
+            assert_eq!((layer_info.crop_x0, layer_info.crop_y0), (10, 5));
+            assert_eq!((layer_info.xsize, layer_info.ysize), (20, 20));
+            assert_eq!(layer_info.blend_info.blendmode, JxlBlendMode::Add);
+        }
+    }
+
+    struct ProgressiveDecoding {
+        image: Vec<u8>,
+        flushes: Vec<JxlDecoderStatus>,
+        /// Copies of the image after each successful flush
+        partial_images: Vec<Vec<u8>>,
+        downsampling_ratios: Vec<usize>,
+    }
+
+    unsafe fn flush_image(dec: *mut JxlDecoder, result: &mut ProgressiveDecoding) {
+        let status = JxlDecoderFlushImage(dec);
+        if status == JxlDecoderStatus::Success {
+            result.partial_images.push(result.image.clone());
+        }
+        result.flushes.push(status);
+    }
+
+    unsafe fn decode_progressive(sample: &[u8], chunk_size: usize) -> ProgressiveDecoding {
+        use DecoderStatus::{
+            BasicInfo, Error, FrameProgression, FullImage, NeedImageOutBuffer, NeedMoreInput,
+            Success,
+        };
+
+        let dec = JxlDecoderCreate(ptr::null());
+        assert!(!dec.is_null());
+
+        let mut status = JxlDecoderSubscribeEvents(
+            dec,
+            jxl_dec_events!(
+                JxlDecoderStatus::BasicInfo,
+                JxlDecoderStatus::FrameProgression,
+                JxlDecoderStatus::FullImage
+            ),
+        );
+        jxl_dec_assert!(status, "Subscribe Events");
+
+        status = JxlDecoderSetProgressiveDetail(dec, JxlProgressiveDetail::Passes);
+        jxl_dec_assert!(status, "Set Progressive Detail");
+
+        let pixel_format = JxlPixelFormat {
+            num_channels: 3,
+            data_type: JxlDataType::Uint8,
+            endianness: JxlEndianness::Native,
+            align: 0,
+        };
+
+        let mut result = ProgressiveDecoding {
+            image: Vec::new(),
+            flushes: Vec::new(),
+            partial_images: Vec::new(),
+            downsampling_ratios: Vec::new(),
+        };
+
+        // Only provide the first chunk, and more when the decoder asks for it
+        let mut end = chunk_size.min(sample.len());
+        status = JxlDecoderSetInput(dec, sample.as_ptr(), end);
+        jxl_dec_assert!(status, "Set input");
+
+        loop {
+            status = JxlDecoderProcessInput(dec);
+
+            match DecoderStatus::try_from(status).unwrap() {
+                NeedMoreInput => {
+                    assert!(end < sample.len(), "Error, already provided all input");
+
+                    // Render what is available so far
+                    if !result.image.is_empty() {
+                        flush_image(dec, &mut result);
+                    }
+
+                    let consumed = end - JxlDecoderReleaseInput(dec);
+                    end = (end + chunk_size).min(sample.len());
+                    status = JxlDecoderSetInput(dec, sample.as_ptr().add(consumed), end - consumed);
+                    jxl_dec_assert!(status, "Set input");
+                }
+
+                NeedImageOutBuffer => {
+                    let mut size = 0;
+                    status = JxlDecoderImageOutBufferSize(dec, &pixel_format, &mut size);
+                    jxl_dec_assert!(status, "BufferSize");
+
+                    result.image.resize(size, 0);
+                    status = JxlDecoderSetImageOutBuffer(
+                        dec,
+                        &pixel_format,
+                        result.image.as_mut_ptr().cast(),
+                        size,
+                    );
+                    jxl_dec_assert!(status, "SetBuffer");
+                }
+
+                FrameProgression => {
+                    result
+                        .downsampling_ratios
+                        .push(JxlDecoderGetIntendedDownsamplingRatio(dec));
+                    flush_image(dec, &mut result);
+                }
+
+                BasicInfo | FullImage => (),
+                Success => break,
+                Error => panic!("Decoder error!"),
+                _ => panic!("Unknown decoder status: {:#?}", status),
+            }
+        }
+
+        JxlDecoderDestroy(dec);
+
+        result
+    }
+
+    #[test]
+    fn test_bindings_progressive() {
+        let sample = std::fs::read("test/sample.jxl").unwrap();
+
+        unsafe {
+            let full = decode_progressive(&sample, sample.len());
+
+            let progressive = decode_progressive(&sample, 16);
+            // One ratio per `FrameProgression` event
+            assert!(!progressive.downsampling_ratios.is_empty());
+            assert!(progressive
+                .flushes
+                .iter()
+                .all(|&s| s == JxlDecoderStatus::Success || s == JxlDecoderStatus::Error));
+
+            // At least one flush renders a partial image, before all of it is decoded
+            assert!(!progressive.partial_images.is_empty());
+            assert!(progressive
+                .partial_images
+                .iter()
+                .any(|partial| *partial != full.image));
+
+            // Each progression step is at least as detailed as the previous one
+            assert!(progressive
+                .downsampling_ratios
+                .iter()
+                .all(|r| [1, 2, 4, 8].contains(r)));
+            assert!(progressive
+                .downsampling_ratios
+                .windows(2)
+                .all(|w| w[0] >= w[1]));
+
+            assert_eq!(progressive.image, full.image);
+        }
+    }
 }
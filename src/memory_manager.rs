@@ -0,0 +1,247 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! [`JxlMemoryManager`] allocating through the Rust global allocator, with accounting and a limit
+//! of the allocations libjxl makes through it.
+
+use std::{
+    alloc::{self, Layout},
+    ffi::c_void,
+    fmt, ptr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crate::common::JxlMemoryManager;
+
+/// Size of the header storing the allocation size, also the alignment of allocations
+/// to match `malloc`
+const HEADER_SIZE: usize = 16;
+
+struct State {
+    limit: usize,
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+/// Memory manager routing libjxl allocations to the Rust global allocator.
+///
+/// It keeps track of the bytes currently allocated and the peak, and fails allocations past its
+/// limit, which makes libjxl return an error instead of using more memory. Clones share the same
+/// accounting, so use one manager per handle to track them separately.
+///
+/// libjxl 0.7 only routes its API-level allocations, such as the decoder and encoder themselves,
+/// through the memory manager. Image buffers and most internal state use `malloc`, so neither the
+/// accounting nor the limit covers them, and the limit alone does not bound the memory used to
+/// decode or encode an image. With the `safe` feature, `safe::Decoder` also checks an estimate
+/// of the image size against the limit, to reject images too large for it.
+///
+/// # Examples
+/// ```
+/// # use jpegxl_sys::{*, memory_manager::MemoryManager};
+/// let memory_manager = MemoryManager::with_limit(64 * 1024 * 1024);
+/// # unsafe {
+/// let dec = JxlDecoderCreate(&memory_manager.as_jxl_memory_manager());
+/// // ... decode
+/// JxlDecoderDestroy(dec);
+/// # }
+/// println!("Peak memory usage: {}", memory_manager.peak());
+/// ```
+#[derive(Clone)]
+pub struct MemoryManager {
+    state: Arc<State>,
+}
+
+impl MemoryManager {
+    /// Create a memory manager without a limit
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_limit(usize::MAX)
+    }
+
+    /// Create a memory manager failing allocations through it past `limit` bytes in total, which
+    /// excludes the image buffers libjxl allocates with `malloc`
+    #[must_use]
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            state: Arc::new(State {
+                limit,
+                current: AtomicUsize::new(0),
+                peak: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Maximum number of bytes allowed to be allocated at once
+    #[must_use]
+    pub fn limit(&self) -> usize {
+        self.state.limit
+    }
+
+    /// Number of bytes currently allocated
+    #[must_use]
+    pub fn current(&self) -> usize {
+        self.state.current.load(Ordering::Relaxed)
+    }
+
+    /// Highest number of bytes allocated at once
+    #[must_use]
+    pub fn peak(&self) -> usize {
+        self.state.peak.load(Ordering::Relaxed)
+    }
+
+    /// Memory manager to pass to libjxl.
+    ///
+    /// It refers to the accounting of `self`, so this manager or one of its clones must outlive
+    /// every handle created with it.
+    #[must_use]
+    pub fn as_jxl_memory_manager(&self) -> JxlMemoryManager {
+        JxlMemoryManager {
+            opaque: Arc::as_ptr(&self.state).cast_mut().cast(),
+            alloc: alloc_func,
+            free: free_func,
+        }
+    }
+}
+
+impl Default for MemoryManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for MemoryManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryManager")
+            .field("limit", &self.limit())
+            .field("current", &self.current())
+            .field("peak", &self.peak())
+            .finish()
+    }
+}
+
+// The base of allocations is aligned to `HEADER_SIZE`, enough for the size stored in the header
+#[allow(clippy::cast_ptr_alignment)]
+unsafe extern "C" fn alloc_func(opaque: *mut c_void, size: usize) -> *mut c_void {
+    let state = &*opaque.cast::<State>();
+    let Some(Ok(layout)) = size
+        .checked_add(HEADER_SIZE)
+        .map(|total| Layout::from_size_align(total, HEADER_SIZE))
+    else {
+        return ptr::null_mut();
+    };
+
+    // Reserve the size first so concurrent allocations can't exceed the limit together
+    let Ok(previous) =
+        state
+            .current
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+                current
+                    .checked_add(size)
+                    .filter(|&total| total <= state.limit)
+            })
+    else {
+        return ptr::null_mut();
+    };
+
+    let base = alloc::alloc(layout);
+    if base.is_null() {
+        state.current.fetch_sub(size, Ordering::Relaxed);
+        return ptr::null_mut();
+    }
+    state.peak.fetch_max(previous + size, Ordering::Relaxed);
+
+    base.cast::<usize>().write(size);
+    base.add(HEADER_SIZE).cast()
+}
+
+#[allow(clippy::cast_ptr_alignment)]
+unsafe extern "C" fn free_func(opaque: *mut c_void, address: *mut c_void) {
+    if address.is_null() {
+        return;
+    }
+
+    let state = &*opaque.cast::<State>();
+    let base = address.cast::<u8>().sub(HEADER_SIZE);
+    let size = base.cast::<usize>().read();
+
+    alloc::dealloc(
+        base,
+        Layout::from_size_align_unchecked(size + HEADER_SIZE, HEADER_SIZE),
+    );
+    state.current.fetch_sub(size, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::decoder::{JxlDecoderCreate, JxlDecoderDestroy};
+
+    #[test]
+    fn test_accounting() {
+        let memory_manager = MemoryManager::with_limit(1000);
+        let jxl = memory_manager.as_jxl_memory_manager();
+
+        unsafe {
+            let a = (jxl.alloc)(jxl.opaque, 600);
+            assert!(!a.is_null());
+            assert_eq!(a as usize % HEADER_SIZE, 0);
+            a.cast::<u8>().write_bytes(0xAA, 600);
+            assert_eq!(memory_manager.current(), 600);
+
+            // Over the limit
+            assert!((jxl.alloc)(jxl.opaque, 600).is_null());
+            assert!((jxl.alloc)(jxl.opaque, usize::MAX).is_null());
+            assert_eq!(memory_manager.current(), 600);
+
+            let b = (jxl.alloc)(jxl.opaque, 400);
+            assert!(!b.is_null());
+            assert_eq!(memory_manager.current(), 1000);
+
+            (jxl.free)(jxl.opaque, a);
+            (jxl.free)(jxl.opaque, ptr::null_mut());
+            assert_eq!(memory_manager.current(), 400);
+
+            (jxl.free)(jxl.opaque, b);
+            assert_eq!(memory_manager.current(), 0);
+        }
+
+        assert_eq!(memory_manager.peak(), 1000);
+    }
+
+    #[test]
+    fn test_decoder_accounting() {
+        let memory_manager = MemoryManager::new();
+
+        unsafe {
+            let dec = JxlDecoderCreate(&memory_manager.as_jxl_memory_manager());
+            assert!(!dec.is_null());
+            assert!(memory_manager.current() > 0);
+            JxlDecoderDestroy(dec);
+        }
+
+        assert_eq!(memory_manager.current(), 0);
+        assert!(memory_manager.peak() > 0);
+
+        let memory_manager = MemoryManager::with_limit(0);
+        unsafe {
+            assert!(JxlDecoderCreate(&memory_manager.as_jxl_memory_manager()).is_null());
+        }
+    }
+}
@@ -0,0 +1,203 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! [`JxlParallelRunner`](crate::common::JxlParallelRunner) running on a [`rayon`] thread pool,
+//! without linking `libjxl_threads` and the C++ runtime.
+
+use std::{
+    ffi::c_void,
+    ptr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use rayon::{prelude::*, ThreadPool};
+
+use crate::common::{
+    JxlParallelRetCode, JxlParallelRunFunction, JxlParallelRunInit, JXL_PARALLEL_RET_RUNNER_ERROR,
+    JXL_PARALLEL_RET_SUCCESS,
+};
+
+/// Parallel runner using a rayon [`ThreadPool`] passed as `runner_opaque`, or the current
+/// rayon thread pool if `runner_opaque` is null.
+///
+/// # Examples
+/// ```
+/// # use jpegxl_sys::{*, rayon_runner::*};
+/// let pool = rayon::ThreadPoolBuilder::new().build().unwrap();
+/// # unsafe {
+/// let dec = JxlDecoderCreate(std::ptr::null());
+/// JxlDecoderSetParallelRunner(dec, rayon_parallel_runner, rayon_runner_opaque(&pool));
+/// # JxlDecoderDestroy(dec);
+/// # }
+/// ```
+///
+/// # Safety
+/// `runner_opaque` must be null or point to a [`ThreadPool`] alive during the call,
+/// and `init` and `func` must be valid to call with `jpegxl_opaque`.
+pub unsafe extern "C" fn rayon_parallel_runner(
+    runner_opaque: *mut c_void,
+    jpegxl_opaque: *mut c_void,
+    init: JxlParallelRunInit,
+    func: JxlParallelRunFunction,
+    start_range: u32,
+    end_range: u32,
+) -> JxlParallelRetCode {
+    let pool = runner_opaque.cast::<ThreadPool>().as_ref();
+
+    let num_threads = pool.map_or_else(rayon::current_num_threads, ThreadPool::current_num_threads);
+    let ret = init(jpegxl_opaque, num_threads);
+    if ret != JXL_PARALLEL_RET_SUCCESS {
+        return ret;
+    }
+
+    // Raw pointers are not `Send`, but libjxl expects it to be shared among threads
+    let opaque = jpegxl_opaque as usize;
+    let failed = AtomicBool::new(false);
+    let run = || {
+        (start_range..end_range).into_par_iter().for_each(|value| {
+            // Always a worker thread of the pool running the iterator
+            match rayon::current_thread_index() {
+                Some(thread_id) if thread_id < num_threads => {
+                    func(opaque as *mut c_void, value, thread_id);
+                }
+                _ => failed.store(true, Ordering::Relaxed),
+            }
+        });
+    };
+
+    match pool {
+        Some(pool) => pool.install(run),
+        None => run(),
+    }
+
+    if failed.load(Ordering::Relaxed) {
+        JXL_PARALLEL_RET_RUNNER_ERROR
+    } else {
+        JXL_PARALLEL_RET_SUCCESS
+    }
+}
+
+/// Opaque pointer of `pool` to pass along with [`rayon_parallel_runner`]
+#[must_use]
+pub fn rayon_runner_opaque(pool: &ThreadPool) -> *mut c_void {
+    ptr::from_ref(pool).cast_mut().cast()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicU32, AtomicUsize},
+        Mutex,
+    };
+
+    use rayon::ThreadPoolBuilder;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Record {
+        num_threads: AtomicUsize,
+        sum: AtomicU32,
+        thread_ids: Mutex<Vec<usize>>,
+    }
+
+    unsafe extern "C" fn init(opaque: *mut c_void, num_threads: usize) -> JxlParallelRetCode {
+        let record = &*opaque.cast::<Record>();
+        record.num_threads.store(num_threads, Ordering::Relaxed);
+        JXL_PARALLEL_RET_SUCCESS
+    }
+
+    unsafe extern "C" fn init_fail(
+        _opaque: *mut c_void,
+        _num_threads: usize,
+    ) -> JxlParallelRetCode {
+        -2
+    }
+
+    unsafe extern "C" fn func(opaque: *mut c_void, value: u32, thread_id: usize) {
+        let record = &*opaque.cast::<Record>();
+        record.sum.fetch_add(value, Ordering::Relaxed);
+        record.thread_ids.lock().unwrap().push(thread_id);
+    }
+
+    #[test]
+    fn test_rayon_runner() {
+        let pool = ThreadPoolBuilder::new().num_threads(3).build().unwrap();
+        let record = Record::default();
+        let opaque = ptr::addr_of!(record).cast_mut().cast();
+
+        let ret = unsafe {
+            rayon_parallel_runner(rayon_runner_opaque(&pool), opaque, init, func, 10, 110)
+        };
+        assert_eq!(ret, JXL_PARALLEL_RET_SUCCESS);
+        assert_eq!(record.num_threads.load(Ordering::Relaxed), 3);
+        assert_eq!(record.sum.load(Ordering::Relaxed), (10..110).sum());
+
+        let thread_ids = record.thread_ids.into_inner().unwrap();
+        assert_eq!(thread_ids.len(), 100);
+        assert!(thread_ids.iter().all(|&id| id < 3));
+    }
+
+    #[test]
+    fn test_rayon_runner_current_pool() {
+        let record = Record::default();
+        let opaque = ptr::addr_of!(record).cast_mut().cast();
+
+        let ret = unsafe { rayon_parallel_runner(ptr::null_mut(), opaque, init, func, 0, 50) };
+        assert_eq!(ret, JXL_PARALLEL_RET_SUCCESS);
+        assert_eq!(
+            record.num_threads.load(Ordering::Relaxed),
+            rayon::current_num_threads()
+        );
+        assert_eq!(record.sum.load(Ordering::Relaxed), (0..50).sum());
+    }
+
+    #[test]
+    fn test_rayon_runner_init_error() {
+        let record = Record::default();
+        let opaque = ptr::addr_of!(record).cast_mut().cast();
+
+        let ret = unsafe { rayon_parallel_runner(ptr::null_mut(), opaque, init_fail, func, 0, 50) };
+        assert_eq!(ret, -2);
+        assert_eq!(record.sum.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "safe")]
+    fn test_rayon_runner_decoding() {
+        use crate::{
+            decoder::{JxlDecoderSetParallelRunner, JxlDecoderStatus},
+            safe::Decoder,
+        };
+
+        let pool = ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        let sample = std::fs::read("test/sample.jxl").unwrap();
+        let mut decoder = Decoder::new().unwrap();
+
+        unsafe {
+            let status = JxlDecoderSetParallelRunner(
+                decoder.as_ptr(),
+                rayon_parallel_runner,
+                rayon_runner_opaque(&pool),
+            );
+            assert_eq!(status, JxlDecoderStatus::Success);
+        }
+
+        let image = decoder.decode(&sample).unwrap();
+        assert_eq!(image.data.len(), 40 * 50 * 3);
+    }
+}
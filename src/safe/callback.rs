@@ -0,0 +1,541 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::{
+    any::Any,
+    ffi::c_void,
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, PoisonError,
+    },
+};
+
+use super::{check_dec_status, DecodeError, PixelType};
+use crate::{
+    common::{JxlEndianness, JxlPixelFormat},
+    decoder::{
+        JxlDecoder, JxlDecoderSetImageOutCallback, JxlDecoderSetMultithreadedImageOutCallback,
+    },
+};
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for super::ImageOutCallback<'_> {}
+    impl<S: Send> Sealed for super::ParallelImageOutCallback<'_, S> {}
+}
+
+/// Image output of the decoder through a callback
+///
+/// This trait is sealed, as the decoder trusts its implementations to catch panics before they
+/// unwind into libjxl.
+pub trait ImageOut: private::Sealed {
+    /// Pixel format the callback receives
+    fn format(&self) -> &JxlPixelFormat;
+
+    /// Set as the image output of the current frame of `dec`,
+    /// in response to `JxlDecoderStatus::NeedImageOutBuffer`
+    ///
+    /// # Errors
+    /// Returns an error if the output of the current frame is already set or the format is not
+    /// supported
+    ///
+    /// # Safety
+    /// `dec` must be a valid decoder, and `self` must outlive its use by the decoder, until the
+    /// frame is fully decoded or the decoder is rewound, reset or destroyed
+    unsafe fn register(&self, dec: *mut JxlDecoder) -> Result<(), DecodeError>;
+
+    /// Check whether the callback has panicked, to call after `JxlDecoderProcessInput` returns
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::CallbackPanicked`] if the callback has panicked
+    fn check(&self) -> Result<(), DecodeError>;
+
+    /// Take the payload of the panic in the callback, which
+    /// [`Events::next_event`](super::Events::next_event) resumes with
+    /// [`std::panic::resume_unwind`]
+    fn take_panic(&self) -> Option<Box<dyn Any + Send>>;
+}
+
+/// Catches panics before they unwind into libjxl, which is undefined behavior
+#[derive(Default)]
+struct PanicGuard {
+    panicked: AtomicBool,
+    payload: Mutex<Option<Box<dyn Any + Send>>>,
+}
+
+impl PanicGuard {
+    /// Run `f` unless a previous call has panicked
+    fn catch<R>(&self, f: impl FnOnce() -> R) -> Option<R> {
+        if self.panicked() {
+            return None;
+        }
+        self.catch_always(f)
+    }
+
+    fn catch_always<R>(&self, f: impl FnOnce() -> R) -> Option<R> {
+        match panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(result) => Some(result),
+            Err(payload) => {
+                *self.payload.lock().unwrap_or_else(PoisonError::into_inner) = Some(payload);
+                self.panicked.store(true, Ordering::Release);
+                None
+            }
+        }
+    }
+
+    fn panicked(&self) -> bool {
+        self.panicked.load(Ordering::Acquire)
+    }
+
+    fn check(&self) -> Result<(), DecodeError> {
+        if self.panicked() {
+            Err(DecodeError::CallbackPanicked)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn take(&self) -> Option<Box<dyn Any + Send>> {
+        self.payload
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+}
+
+fn pixel_format<T: PixelType>(num_channels: u32) -> JxlPixelFormat {
+    JxlPixelFormat {
+        num_channels,
+        data_type: T::DATA_TYPE,
+        endianness: JxlEndianness::Native,
+        align: 0,
+    }
+}
+
+type Callback<'a> = dyn FnMut(usize, usize, usize, *const c_void) + Send + 'a;
+
+struct State<'a> {
+    callback: Mutex<Box<Callback<'a>>>,
+    guard: PanicGuard,
+}
+
+/// Closure receiving the decoded pixels through `JxlDecoderSetImageOutCallback`
+///
+/// The closure is called with the coordinate of the first pixel and interleaved samples of
+/// consecutive pixels in a row. A panic in the closure is caught before it reaches libjxl and the
+/// following calls are skipped, then [`Events::next_event`](super::Events::next_event) resumes
+/// it once libjxl returns.
+pub struct ImageOutCallback<'a> {
+    state: Box<State<'a>>,
+    format: JxlPixelFormat,
+}
+
+impl<'a> ImageOutCallback<'a> {
+    /// Receive pixels of `num_channels` samples of type `T` with `callback`.
+    ///
+    /// libjxl may call it from multiple threads of the parallel runner, the calls are serialized.
+    /// Use [`ParallelImageOutCallback`] to process rows concurrently.
+    pub fn new<T, F>(num_channels: u32, mut callback: F) -> Self
+    where
+        T: PixelType,
+        F: FnMut(usize, usize, &[T]) + Send + 'a,
+    {
+        let samples_per_pixel = num_channels as usize;
+        let callback = move |x, y, num_pixels: usize, pixels: *const c_void| {
+            // libjxl provides `num_pixels` pixels in the requested format
+            let pixels = unsafe {
+                slice::from_raw_parts(pixels.cast::<T>(), num_pixels * samples_per_pixel)
+            };
+            callback(x, y, pixels);
+        };
+
+        Self {
+            state: Box::new(State {
+                callback: Mutex::new(Box::new(callback)),
+                guard: PanicGuard::default(),
+            }),
+            format: pixel_format::<T>(num_channels),
+        }
+    }
+}
+
+impl ImageOut for ImageOutCallback<'_> {
+    fn format(&self) -> &JxlPixelFormat {
+        &self.format
+    }
+
+    unsafe fn register(&self, dec: *mut JxlDecoder) -> Result<(), DecodeError> {
+        check_dec_status(JxlDecoderSetImageOutCallback(
+            dec,
+            ptr::addr_of!(self.format),
+            trampoline,
+            ptr::addr_of!(*self.state).cast_mut().cast(),
+        ))
+    }
+
+    fn check(&self) -> Result<(), DecodeError> {
+        self.state.guard.check()
+    }
+
+    fn take_panic(&self) -> Option<Box<dyn Any + Send>> {
+        self.state.guard.take()
+    }
+}
+
+extern "C" fn trampoline(
+    opaque: *mut c_void,
+    x: usize,
+    y: usize,
+    num_pixels: usize,
+    pixels: *const c_void,
+) {
+    let state = unsafe { &*opaque.cast::<State>() };
+    state.guard.catch(|| {
+        let mut callback = state
+            .callback
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        // The call holding the lock before may have panicked
+        if !state.guard.panicked() {
+            callback(x, y, num_pixels, pixels);
+        }
+    });
+}
+
+type InitCallback<'a, S> = dyn Fn(usize) -> S + Sync + 'a;
+type RunCallback<'a, S> = dyn Fn(&mut S, usize, usize, usize, *const c_void) + Sync + 'a;
+
+struct ParallelState<'a, S> {
+    init: Box<InitCallback<'a, S>>,
+    run: Box<RunCallback<'a, S>>,
+    guard: PanicGuard,
+}
+
+/// States of the worker threads for the current frame
+struct Workers<'s, 'a, S> {
+    state: &'s ParallelState<'a, S>,
+    states: Vec<Mutex<S>>,
+}
+
+/// Closures receiving the decoded pixels concurrently through
+/// `JxlDecoderSetMultithreadedImageOutCallback`
+///
+/// Before the pixels of a frame are output, `init` creates a state for each thread of the
+/// parallel runner. It is passed to the calls of `run` on that thread and dropped once the frame
+/// is done. Panics are handled as in [`ImageOutCallback`].
+pub struct ParallelImageOutCallback<'a, S> {
+    state: Box<ParallelState<'a, S>>,
+    format: JxlPixelFormat,
+}
+
+impl<'a, S: Send> ParallelImageOutCallback<'a, S> {
+    /// Receive pixels of `num_channels` samples of type `T` with `run`, using the state `init`
+    /// creates for each worker thread.
+    ///
+    /// `init` is called with the maximum number of pixels in a call to `run`, to preallocate
+    /// buffers for example. `run` is called with the state of the current thread, the coordinate
+    /// of the first pixel and interleaved samples of consecutive pixels in a row.
+    pub fn new<T, I, R>(num_channels: u32, init: I, run: R) -> Self
+    where
+        T: PixelType,
+        I: Fn(usize) -> S + Sync + 'a,
+        R: Fn(&mut S, usize, usize, &[T]) + Sync + 'a,
+    {
+        let samples_per_pixel = num_channels as usize;
+        let run = move |state: &mut S, x, y, num_pixels: usize, pixels: *const c_void| {
+            // libjxl provides `num_pixels` pixels in the requested format
+            let pixels = unsafe {
+                slice::from_raw_parts(pixels.cast::<T>(), num_pixels * samples_per_pixel)
+            };
+            run(state, x, y, pixels);
+        };
+
+        Self {
+            state: Box::new(ParallelState {
+                init: Box::new(init),
+                run: Box::new(run),
+                guard: PanicGuard::default(),
+            }),
+            format: pixel_format::<T>(num_channels),
+        }
+    }
+}
+
+impl<S: Send> ImageOut for ParallelImageOutCallback<'_, S> {
+    fn format(&self) -> &JxlPixelFormat {
+        &self.format
+    }
+
+    unsafe fn register(&self, dec: *mut JxlDecoder) -> Result<(), DecodeError> {
+        check_dec_status(JxlDecoderSetMultithreadedImageOutCallback(
+            dec,
+            ptr::addr_of!(self.format),
+            parallel_init::<S>,
+            parallel_run::<S>,
+            parallel_destroy::<S>,
+            ptr::addr_of!(*self.state).cast_mut().cast(),
+        ))
+    }
+
+    fn check(&self) -> Result<(), DecodeError> {
+        self.state.guard.check()
+    }
+
+    fn take_panic(&self) -> Option<Box<dyn Any + Send>> {
+        self.state.guard.take()
+    }
+}
+
+extern "C" fn parallel_init<S>(
+    init_opaque: *mut c_void,
+    num_threads: usize,
+    num_pixels_per_thread: usize,
+) -> *mut c_void {
+    let state = unsafe { &*init_opaque.cast::<ParallelState<S>>() };
+    state
+        .guard
+        .catch(|| {
+            let states = (0..num_threads)
+                .map(|_| Mutex::new((state.init)(num_pixels_per_thread)))
+                .collect();
+            Box::into_raw(Box::new(Workers { state, states })).cast()
+        })
+        // libjxl fails the decoding on a null pointer
+        .unwrap_or(ptr::null_mut())
+}
+
+extern "C" fn parallel_run<S>(
+    run_opaque: *mut c_void,
+    thread_id: usize,
+    x: usize,
+    y: usize,
+    num_pixels: usize,
+    pixels: *const c_void,
+) {
+    let workers = unsafe { &*run_opaque.cast::<Workers<S>>() };
+    workers.state.guard.catch(|| {
+        // A thread id is only used by one thread at a time, so the lock is uncontended
+        let mut state = workers.states[thread_id]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        (workers.state.run)(&mut state, x, y, num_pixels, pixels);
+    });
+}
+
+extern "C" fn parallel_destroy<S>(run_opaque: *mut c_void) {
+    if run_opaque.is_null() {
+        return;
+    }
+
+    let workers = unsafe { Box::from_raw(run_opaque.cast::<Workers<S>>()) };
+    let state = workers.state;
+    // The states are dropped even after a panic
+    state.guard.catch_always(|| drop(workers));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{atomic::AtomicUsize, Barrier};
+
+    use crate::{
+        decoder::JxlDecoderStatus,
+        jxl_dec_events,
+        safe::{Decoder, Event},
+    };
+
+    fn decode_with(
+        decoder: &mut Decoder,
+        sample: &[u8],
+        callback: impl ImageOut,
+    ) -> Result<(), DecodeError> {
+        let mut callback = Some(callback);
+        let mut events = decoder.events(sample, jxl_dec_events!(JxlDecoderStatus::FullImage))?;
+        loop {
+            match events.next_event()? {
+                Event::NeedImageOutBuffer => {
+                    events.set_image_out_callback(callback.take().unwrap())?;
+                }
+                Event::Finished => return Ok(()),
+                _ => (),
+            }
+        }
+    }
+
+    #[test]
+    fn test_callback() -> Result<(), DecodeError> {
+        let sample = std::fs::read("test/sample.jxl").unwrap();
+        let mut decoder = Decoder::new()?;
+        let expected = decoder.decode(&sample)?.data;
+
+        let mut pixels = vec![0u8; 40 * 50 * 3];
+        let mut calls = 0;
+        let callback = ImageOutCallback::new(3, |x, y, row: &[u8]| {
+            let start = (y * 40 + x) * 3;
+            pixels[start..start + row.len()].copy_from_slice(row);
+            calls += 1;
+        });
+        decode_with(&mut decoder, &sample, callback)?;
+
+        assert!(calls > 0);
+        assert_eq!(pixels, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_callback_panic() -> Result<(), DecodeError> {
+        let sample = std::fs::read("test/sample.jxl").unwrap();
+        let mut decoder = Decoder::new()?;
+
+        let mut events = decoder.events(&sample, jxl_dec_events!(JxlDecoderStatus::FullImage))?;
+        assert_eq!(events.next_event()?, Event::NeedImageOutBuffer);
+        events.set_image_out_callback(ImageOutCallback::new(3, |_, _, _: &[u8]| {
+            panic!("Callback panic");
+        }))?;
+        let payload = panic::catch_unwind(AssertUnwindSafe(|| events.next_event())).unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"Callback panic"));
+        // Only resumed once
+        assert_eq!(
+            events.next_event().unwrap_err(),
+            DecodeError::CallbackPanicked
+        );
+        drop(events);
+
+        // The decoder is still usable after the panic
+        assert!(decoder.decode(&sample).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_callback_payload() {
+        let callback = ImageOutCallback::new(3, |x, _, _: &[u8]| panic!("Callback panic at {x}"));
+        let opaque = ptr::addr_of!(*callback.state).cast_mut().cast();
+        let pixels = [0u8; 3];
+        trampoline(opaque, 7, 0, 1, pixels.as_ptr().cast());
+
+        assert_eq!(callback.check(), Err(DecodeError::CallbackPanicked));
+        let payload = callback.take_panic().unwrap();
+        assert_eq!(
+            payload.downcast_ref::<String>().map(String::as_str),
+            Some("Callback panic at 7")
+        );
+        assert!(callback.take_panic().is_none());
+    }
+
+    #[test]
+    fn test_callback_after_panic() {
+        let calls = AtomicUsize::new(0);
+        let callback = ImageOutCallback::new(3, |_, _, _: &[u8]| {
+            calls.fetch_add(1, Ordering::Relaxed);
+        });
+        let state = &*callback.state;
+        // As an address, to be sent to another thread
+        let opaque = ptr::addr_of!(*state) as usize;
+        let pixels = [0u8; 3];
+        let call = || trampoline(opaque as *mut c_void, 0, 0, 1, pixels.as_ptr().cast());
+
+        // A call races with the call holding the lock, which panics. Whether it passes the check
+        // before taking the lock or not, it can only run after the panic, so it must skip the
+        // callback under any interleaving.
+        let lock = state.callback.lock().unwrap();
+        let started = Barrier::new(2);
+        std::thread::scope(|scope| {
+            let waiting = scope.spawn(|| {
+                started.wait();
+                call();
+            });
+            started.wait();
+            state.guard.catch_always(|| panic!("Callback panic"));
+            drop(lock);
+            waiting.join().unwrap();
+        });
+        call();
+
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+        assert_eq!(callback.check(), Err(DecodeError::CallbackPanicked));
+    }
+
+    #[test]
+    #[cfg(feature = "threads")]
+    fn test_parallel_callback() -> Result<(), DecodeError> {
+        use crate::parallel_runner::{
+            JxlThreadParallelRunner, JxlThreadParallelRunnerCreate, JxlThreadParallelRunnerDestroy,
+        };
+
+        let sample = std::fs::read("test/sample.jxl").unwrap();
+        let mut decoder = Decoder::new()?;
+        let expected = decoder.decode(&sample)?.data;
+
+        let runner = unsafe { JxlThreadParallelRunnerCreate(ptr::null(), 4) };
+        unsafe { decoder.set_parallel_runner(JxlThreadParallelRunner, runner)? };
+
+        let pixels = Mutex::new(vec![0u8; 40 * 50 * 3]);
+        let num_states = Mutex::new(0);
+        let callback = ParallelImageOutCallback::new(
+            3,
+            |num_pixels| {
+                *num_states.lock().unwrap() += 1;
+                Vec::with_capacity(num_pixels * 3)
+            },
+            |buffer: &mut Vec<u8>, x, y, row: &[u8]| {
+                // Scratch space of the thread, for a color conversion for example
+                assert!(row.len() <= buffer.capacity());
+                buffer.clear();
+                buffer.extend_from_slice(row);
+
+                let start = (y * 40 + x) * 3;
+                pixels.lock().unwrap()[start..start + row.len()].copy_from_slice(buffer);
+            },
+        );
+        let result = decode_with(&mut decoder, &sample, callback);
+        drop(decoder);
+        unsafe { JxlThreadParallelRunnerDestroy(runner) };
+        result?;
+
+        assert!(num_states.into_inner().unwrap() > 0);
+        assert_eq!(pixels.into_inner().unwrap(), expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_callback_panic() -> Result<(), DecodeError> {
+        let sample = std::fs::read("test/sample.jxl").unwrap();
+        let mut decoder = Decoder::new()?;
+
+        let callback = ParallelImageOutCallback::new(
+            3,
+            |_| (),
+            |(), x, y, _: &[u8]| panic!("Callback panic at {x}, {y}"),
+        );
+        let payload = panic::catch_unwind(AssertUnwindSafe(|| {
+            decode_with(&mut decoder, &sample, callback)
+        }))
+        .unwrap_err();
+        assert!(payload
+            .downcast_ref::<String>()
+            .is_some_and(|message| message.starts_with("Callback panic at ")));
+        assert!(decoder.decode(&sample).is_ok());
+
+        Ok(())
+    }
+}
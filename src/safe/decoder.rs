@@ -0,0 +1,710 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::{
+    ffi::c_void,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    panic,
+    ptr::{self, NonNull},
+};
+
+use super::{check_dec_status, DecodeError, ImageOut};
+use crate::{
+    common::{
+        JxlBasicInfo, JxlDataType, JxlEndianness, JxlExtraChannelInfo, JxlFrameHeader,
+        JxlParallelRunner, JxlPixelFormat,
+    },
+    decoder::{
+        JxlDecoder, JxlDecoderCreate, JxlDecoderDestroy, JxlDecoderEvents, JxlDecoderFlushImage,
+        JxlDecoderGetBasicInfo, JxlDecoderGetExtraChannelInfo, JxlDecoderGetFrameHeader,
+        JxlDecoderGetFrameName, JxlDecoderImageOutBufferSize, JxlDecoderProcessInput,
+        JxlDecoderReleaseInput, JxlDecoderReset, JxlDecoderRewind, JxlDecoderSetCoalescing,
+        JxlDecoderSetImageOutBuffer, JxlDecoderSetInput, JxlDecoderSetKeepOrientation,
+        JxlDecoderSetParallelRunner, JxlDecoderStatus, JxlDecoderSubscribeEvents,
+    },
+    memory_manager::MemoryManager,
+};
+
+/// Events returned by [`Events::next_event`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    BasicInfo,
+    ColorEncoding,
+    PreviewImage,
+    Frame,
+    FullImage,
+    JpegReconstruction,
+    Box,
+    FrameProgression,
+    NeedPreviewOutBuffer,
+    NeedImageOutBuffer,
+    JpegNeedMoreOutput,
+    BoxNeedMoreOutput,
+    /// The decoding is finished
+    Finished,
+}
+
+impl TryFrom<JxlDecoderStatus> for Event {
+    type Error = DecodeError;
+
+    fn try_from(status: JxlDecoderStatus) -> Result<Self, Self::Error> {
+        use JxlDecoderStatus as S;
+
+        Ok(match status {
+            S::Success => Self::Finished,
+            S::BasicInfo => Self::BasicInfo,
+            S::ColorEncoding => Self::ColorEncoding,
+            S::PreviewImage => Self::PreviewImage,
+            S::Frame => Self::Frame,
+            S::FullImage => Self::FullImage,
+            S::JpegReconstruction => Self::JpegReconstruction,
+            S::Box => Self::Box,
+            S::FrameProgression => Self::FrameProgression,
+            S::NeedPreviewOutBuffer => Self::NeedPreviewOutBuffer,
+            S::NeedImageOutBuffer => Self::NeedImageOutBuffer,
+            S::JpegNeedMoreOutput => Self::JpegNeedMoreOutput,
+            S::BoxNeedMoreOutput => Self::BoxNeedMoreOutput,
+            S::Error => return Err(DecodeError::GenericError),
+            S::NeedMoreInput => return Err(DecodeError::NeedMoreInput),
+            _ => return Err(DecodeError::UnexpectedStatus(status)),
+        })
+    }
+}
+
+/// A decoded image
+#[derive(Debug, Clone)]
+pub struct Image {
+    /// Basic information of the image
+    pub info: JxlBasicInfo,
+    /// Pixel format of `data`
+    pub format: JxlPixelFormat,
+    /// Interleaved pixels in `format`
+    pub data: Vec<u8>,
+}
+
+/// Owned `JxlDecoder`, destroyed on drop
+pub struct Decoder {
+    ptr: NonNull<JxlDecoder>,
+    // Keep the accounting alive until the decoder is destroyed
+    memory_manager: Option<MemoryManager>,
+}
+
+// The decoder is not shared with anything else, it can be moved between threads
+unsafe impl Send for Decoder {}
+
+impl Decoder {
+    /// Create a decoder with the default memory manager
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::CannotCreateDecoder`] if libjxl fails to create one
+    pub fn new() -> Result<Self, DecodeError> {
+        NonNull::new(unsafe { JxlDecoderCreate(ptr::null()) })
+            .map(|ptr| Self {
+                ptr,
+                memory_manager: None,
+            })
+            .ok_or(DecodeError::CannotCreateDecoder)
+    }
+
+    /// Create a decoder allocating through `memory_manager`
+    ///
+    /// As libjxl allocates the image planes with `malloc`, the decoder also checks an estimate of
+    /// their size, and of the output buffer it allocates, against the remaining memory of the
+    /// manager, failing with [`DecodeError::MemoryLimitExceeded`] once the basic info is decoded.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::CannotCreateDecoder`] if libjxl fails to create one, including when
+    /// the memory limit is too low
+    pub fn with_memory_manager(memory_manager: &MemoryManager) -> Result<Self, DecodeError> {
+        NonNull::new(unsafe { JxlDecoderCreate(&memory_manager.as_jxl_memory_manager()) })
+            .map(|ptr| Self {
+                ptr,
+                memory_manager: Some(memory_manager.clone()),
+            })
+            .ok_or(DecodeError::CannotCreateDecoder)
+    }
+
+    /// Bytes left in the memory manager
+    fn memory_budget(&self) -> Option<u64> {
+        let memory_manager = self.memory_manager.as_ref()?;
+        let budget = memory_manager
+            .limit()
+            .saturating_sub(memory_manager.current());
+        Some(u64::try_from(budget).unwrap_or(u64::MAX))
+    }
+
+    /// Raw pointer to use with the functions in [`crate::decoder`]
+    #[must_use]
+    pub fn as_ptr(&self) -> *mut JxlDecoder {
+        self.ptr.as_ptr()
+    }
+
+    /// Reset the decoder, including all its settings
+    pub fn reset(&mut self) {
+        unsafe { JxlDecoderReset(self.as_ptr()) }
+    }
+
+    /// Set the parallel runner for multithreading
+    ///
+    /// # Errors
+    /// Returns an error if the decoding has already started
+    ///
+    /// # Safety
+    /// `runner_opaque` must be valid for `runner` until the decoder is destroyed or reset
+    pub unsafe fn set_parallel_runner(
+        &mut self,
+        runner: JxlParallelRunner,
+        runner_opaque: *mut c_void,
+    ) -> Result<(), DecodeError> {
+        check_dec_status(JxlDecoderSetParallelRunner(
+            self.as_ptr(),
+            runner,
+            runner_opaque,
+        ))
+    }
+
+    /// Keep the orientation as in the bitstream instead of applying it
+    ///
+    /// # Errors
+    /// Returns an error if the decoding has already started
+    pub fn set_keep_orientation(&mut self, keep_orientation: bool) -> Result<(), DecodeError> {
+        check_dec_status(unsafe { JxlDecoderSetKeepOrientation(self.as_ptr(), keep_orientation) })
+    }
+
+    /// Whether to blend frames and layers into full images, default to `true`
+    ///
+    /// # Errors
+    /// Returns an error if the decoding has already started
+    pub fn set_coalescing(&mut self, coalescing: bool) -> Result<(), DecodeError> {
+        check_dec_status(unsafe { JxlDecoderSetCoalescing(self.as_ptr(), coalescing) })
+    }
+
+    /// Start decoding `data` step by step, reporting the subscribed `events`
+    ///
+    /// The decoder is rewound when the returned [`Events`] is dropped, keeping its settings.
+    ///
+    /// # Errors
+    /// Returns an error if `events` is invalid
+    pub fn events<'a>(
+        &'a mut self,
+        data: &'a [u8],
+        events: JxlDecoderEvents,
+    ) -> Result<Events<'a>, DecodeError> {
+        // The memory limit is checked on the basic info, even if not reported
+        let report_basic_info = events.contains(JxlDecoderEvents::BASIC_INFO);
+        let events = if self.memory_manager.is_some() {
+            events | JxlDecoderEvents::BASIC_INFO
+        } else {
+            events
+        };
+
+        unsafe {
+            check_dec_status(JxlDecoderSubscribeEvents(self.as_ptr(), events))?;
+            check_dec_status(JxlDecoderSetInput(self.as_ptr(), data.as_ptr(), data.len()))?;
+        }
+
+        Ok(Events {
+            dec: self,
+            image: Vec::new(),
+            image_in_use: false,
+            callback: None,
+            report_basic_info,
+            planes_size: 0,
+            _data: PhantomData,
+        })
+    }
+
+    /// Decode the first frame of `data`, in a pixel format picked from the basic info
+    ///
+    /// Samples are `u8` up to 8 bits, `u16` up to 16 bits and `f32` for floating point images,
+    /// with an alpha channel if the image has one.
+    ///
+    /// # Errors
+    /// Returns an error if `data` is not a complete JPEG XL image
+    pub fn decode(&mut self, data: &[u8]) -> Result<Image, DecodeError> {
+        self.decode_internal(data, None)
+    }
+
+    /// Decode the first frame of `data` in `format`
+    ///
+    /// # Errors
+    /// Returns an error if `data` is not a complete JPEG XL image or `format` is not supported
+    pub fn decode_with_format(
+        &mut self,
+        data: &[u8],
+        format: &JxlPixelFormat,
+    ) -> Result<Image, DecodeError> {
+        self.decode_internal(data, Some(format.clone()))
+    }
+
+    fn decode_internal(
+        &mut self,
+        data: &[u8],
+        mut format: Option<JxlPixelFormat>,
+    ) -> Result<Image, DecodeError> {
+        let mut events = self.events(
+            data,
+            JxlDecoderEvents::BASIC_INFO | JxlDecoderEvents::FULL_IMAGE,
+        )?;
+        let mut info = None;
+
+        loop {
+            match events.next_event()? {
+                Event::BasicInfo => {
+                    let basic_info = events.basic_info()?;
+                    format.get_or_insert_with(|| default_pixel_format(&basic_info));
+                    info = Some(basic_info);
+                }
+                Event::NeedImageOutBuffer => {
+                    let format = format.as_ref().ok_or(DecodeError::UnexpectedStatus(
+                        JxlDecoderStatus::NeedImageOutBuffer,
+                    ))?;
+                    events.set_image_out_buffer(format)?;
+                }
+                Event::FullImage => {
+                    return match (info, format, events.take_image()) {
+                        (Some(info), Some(format), Some(data)) => Ok(Image { info, format, data }),
+                        _ => Err(DecodeError::UnexpectedStatus(JxlDecoderStatus::FullImage)),
+                    }
+                }
+                Event::Finished => {
+                    return Err(DecodeError::UnexpectedStatus(JxlDecoderStatus::Success))
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        unsafe { JxlDecoderDestroy(self.as_ptr()) }
+    }
+}
+
+fn default_pixel_format(info: &JxlBasicInfo) -> JxlPixelFormat {
+    let data_type = if info.exponent_bits_per_sample > 0 {
+        JxlDataType::Float
+    } else if info.bits_per_sample > 8 {
+        JxlDataType::Uint16
+    } else {
+        JxlDataType::Uint8
+    };
+
+    JxlPixelFormat {
+        num_channels: info.num_color_channels + u32::from(info.alpha_bits > 0),
+        data_type,
+        endianness: JxlEndianness::Native,
+        align: 0,
+    }
+}
+
+/// Estimated size of the image planes libjxl allocates outside of the memory manager to decode
+/// an image of `info`: a 32-bit float plane per channel, twice for animations, which keep the
+/// previous frame to blend with
+fn planes_size(info: &JxlBasicInfo) -> u64 {
+    let pixels = u64::from(info.xsize) * u64::from(info.ysize);
+    let channels = u64::from(info.num_color_channels) + u64::from(info.num_extra_channels);
+    let frames = if bool::from(info.have_animation) {
+        2
+    } else {
+        1
+    };
+    pixels.saturating_mul(channels).saturating_mul(4 * frames)
+}
+
+/// Step-wise decoding of an input, created by [`Decoder::events`]
+pub struct Events<'a> {
+    dec: &'a mut Decoder,
+    image: Vec<u8>,
+    image_in_use: bool,
+    callback: Option<Box<dyn ImageOut + 'a>>,
+    /// Whether [`Event::BasicInfo`] was subscribed, rather than only for the memory limit
+    report_basic_info: bool,
+    /// Estimate of the image planes, once the basic info is decoded
+    planes_size: u64,
+    _data: PhantomData<&'a [u8]>,
+}
+
+impl<'a> Events<'a> {
+    /// Decode until the next event
+    ///
+    /// # Errors
+    /// Returns an error if the decoding fails, the input ends too early, the image output
+    /// callback panicked before or the image is too large for the memory limit
+    ///
+    /// # Panics
+    /// Resumes the panic of the image output callback
+    pub fn next_event(&mut self) -> Result<Event, DecodeError> {
+        loop {
+            let status = unsafe { JxlDecoderProcessInput(self.dec.as_ptr()) };
+            if let Some(callback) = &self.callback {
+                if let Some(payload) = callback.take_panic() {
+                    panic::resume_unwind(payload);
+                }
+                callback.check()?;
+            }
+
+            let event = Event::try_from(status)?;
+            match event {
+                Event::BasicInfo => {
+                    if let Some(budget) = self.dec.memory_budget() {
+                        self.planes_size = planes_size(&self.basic_info()?);
+                        if self.planes_size > budget {
+                            return Err(DecodeError::MemoryLimitExceeded);
+                        }
+                    }
+                    if !self.report_basic_info {
+                        continue;
+                    }
+                }
+                Event::FullImage => self.image_in_use = false,
+                _ => (),
+            }
+            return Ok(event);
+        }
+    }
+
+    /// Basic information of the image, available after [`Event::BasicInfo`]
+    ///
+    /// # Errors
+    /// Returns an error if the basic info is not decoded yet
+    pub fn basic_info(&self) -> Result<JxlBasicInfo, DecodeError> {
+        let mut info = MaybeUninit::uninit();
+        unsafe {
+            check_dec_status(JxlDecoderGetBasicInfo(self.dec.as_ptr(), info.as_mut_ptr()))?;
+            Ok(info.assume_init())
+        }
+    }
+
+    /// Information of the extra channel at `index`, available after [`Event::BasicInfo`]
+    ///
+    /// # Errors
+    /// Returns an error if the basic info is not decoded yet or `index` is out of range
+    pub fn extra_channel_info(&self, index: usize) -> Result<JxlExtraChannelInfo, DecodeError> {
+        let mut info = MaybeUninit::uninit();
+        unsafe {
+            check_dec_status(JxlDecoderGetExtraChannelInfo(
+                self.dec.as_ptr(),
+                index,
+                info.as_mut_ptr(),
+            ))?;
+            Ok(info.assume_init())
+        }
+    }
+
+    /// Header of the current frame, available after [`Event::Frame`]
+    ///
+    /// # Errors
+    /// Returns an error if there is no current frame
+    pub fn frame_header(&self) -> Result<JxlFrameHeader, DecodeError> {
+        let mut header = MaybeUninit::uninit();
+        unsafe {
+            check_dec_status(JxlDecoderGetFrameHeader(
+                self.dec.as_ptr(),
+                header.as_mut_ptr(),
+            ))?;
+            Ok(header.assume_init())
+        }
+    }
+
+    /// Name of the current frame, available after [`Event::Frame`]
+    ///
+    /// # Errors
+    /// Returns an error if there is no current frame
+    pub fn frame_name(&self) -> Result<String, DecodeError> {
+        let mut name = vec![0u8; self.frame_header()?.name_length as usize + 1];
+        check_dec_status(unsafe {
+            JxlDecoderGetFrameName(self.dec.as_ptr(), name.as_mut_ptr().cast(), name.len())
+        })?;
+        name.pop();
+        Ok(String::from_utf8_lossy(&name).into_owned())
+    }
+
+    /// Allocate the output buffer of the current frame in `format`,
+    /// in response to [`Event::NeedImageOutBuffer`]
+    ///
+    /// # Errors
+    /// Returns an error if a buffer is already set for the current frame, `format` is not
+    /// supported or the buffer doesn't fit in the memory limit along with the image planes
+    pub fn set_image_out_buffer(&mut self, format: &JxlPixelFormat) -> Result<(), DecodeError> {
+        if self.image_in_use {
+            return Err(DecodeError::UnexpectedStatus(
+                JxlDecoderStatus::NeedImageOutBuffer,
+            ));
+        }
+
+        let mut size = 0;
+        unsafe {
+            check_dec_status(JxlDecoderImageOutBufferSize(
+                self.dec.as_ptr(),
+                format,
+                ptr::addr_of_mut!(size),
+            ))?;
+            if let Some(budget) = self.dec.memory_budget() {
+                let needed = u64::try_from(size).unwrap_or(u64::MAX);
+                if self.planes_size.saturating_add(needed) > budget {
+                    return Err(DecodeError::MemoryLimitExceeded);
+                }
+            }
+            self.image.resize(size, 0);
+            check_dec_status(JxlDecoderSetImageOutBuffer(
+                self.dec.as_ptr(),
+                format,
+                self.image.as_mut_ptr().cast(),
+                size,
+            ))?;
+        }
+
+        self.image_in_use = true;
+        Ok(())
+    }
+
+    /// Receive the pixels of the current frame with `callback` instead of an output buffer,
+    /// in response to [`Event::NeedImageOutBuffer`]
+    ///
+    /// # Errors
+    /// Returns an error if an output is already set for the current frame or the format is not
+    /// supported
+    pub fn set_image_out_callback(
+        &mut self,
+        callback: impl ImageOut + 'a,
+    ) -> Result<(), DecodeError> {
+        if self.image_in_use {
+            return Err(DecodeError::UnexpectedStatus(
+                JxlDecoderStatus::NeedImageOutBuffer,
+            ));
+        }
+
+        // The previous callback is not in use anymore, and the new one lives until the next
+        // frame or the decoder is rewound
+        let callback = self.callback.insert(Box::new(callback));
+        unsafe { callback.register(self.dec.as_ptr())? };
+
+        self.image_in_use = true;
+        Ok(())
+    }
+
+    /// Output image of the current frame, partially decoded until [`Event::FullImage`]
+    #[must_use]
+    pub fn image(&self) -> &[u8] {
+        &self.image
+    }
+
+    /// Take the output image, only after [`Event::FullImage`] since the decoder writes into the
+    /// buffer until then
+    pub fn take_image(&mut self) -> Option<Vec<u8>> {
+        (!self.image_in_use).then(|| mem::take(&mut self.image))
+    }
+
+    /// Write the partially decoded pixels into the output image
+    ///
+    /// # Errors
+    /// Returns an error if there is not enough data to render anything yet
+    pub fn flush(&mut self) -> Result<(), DecodeError> {
+        check_dec_status(unsafe { JxlDecoderFlushImage(self.dec.as_ptr()) })
+    }
+}
+
+impl Drop for Events<'_> {
+    fn drop(&mut self) {
+        // Rewinding drops the references to the input and the output buffer
+        unsafe {
+            JxlDecoderReleaseInput(self.dec.as_ptr());
+            JxlDecoderRewind(self.dec.as_ptr());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::jxl_dec_events;
+
+    #[test]
+    fn test_decode() -> Result<(), DecodeError> {
+        let sample = std::fs::read("test/sample.jxl").unwrap();
+        let mut decoder = Decoder::new()?;
+
+        let image = decoder.decode(&sample)?;
+        assert_eq!((image.info.xsize, image.info.ysize), (40, 50));
+        assert_eq!(image.format.num_channels, 3);
+        assert_eq!(image.format.data_type, JxlDataType::Uint8);
+        assert_eq!(image.data.len(), 40 * 50 * 3);
+
+        // The decoder can be reused
+        let format = JxlPixelFormat {
+            num_channels: 4,
+            data_type: JxlDataType::Uint16,
+            endianness: JxlEndianness::Native,
+            align: 0,
+        };
+        let image = decoder.decode_with_format(&sample, &format)?;
+        assert_eq!(image.format, format);
+        assert_eq!(image.data.len(), 40 * 50 * 4 * 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_error() -> Result<(), DecodeError> {
+        let sample = std::fs::read("test/sample.jxl").unwrap();
+        let mut decoder = Decoder::new()?;
+
+        assert_eq!(
+            decoder.decode(&sample[..sample.len() / 2]).unwrap_err(),
+            DecodeError::NeedMoreInput
+        );
+        assert_eq!(
+            decoder.decode(b"Not a JPEG XL image").unwrap_err(),
+            DecodeError::GenericError
+        );
+        assert!(decoder.decode(&sample).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_status() {
+        let status = JxlDecoderStatus(0x10000);
+        assert_eq!(
+            Event::try_from(status),
+            Err(DecodeError::UnexpectedStatus(status))
+        );
+        assert_eq!(
+            check_dec_status(status),
+            Err(DecodeError::UnexpectedStatus(status))
+        );
+    }
+
+    #[test]
+    fn test_memory_limit() -> Result<(), DecodeError> {
+        let sample = std::fs::read("test/sample.jxl").unwrap();
+
+        let memory_manager = MemoryManager::new();
+        let mut decoder = Decoder::with_memory_manager(&memory_manager)?;
+        let created = memory_manager.current();
+        assert!(created > 0);
+        decoder.decode(&sample)?;
+        assert!(memory_manager.peak() >= created);
+        drop(decoder);
+        assert_eq!(memory_manager.current(), 0);
+
+        // Not enough to create the decoder
+        let memory_manager = MemoryManager::with_limit(created - 1);
+        assert!(matches!(
+            Decoder::with_memory_manager(&memory_manager),
+            Err(DecodeError::CannotCreateDecoder)
+        ));
+
+        // Enough to create the decoder, but not for the 40x50 RGB planes
+        let memory_manager = MemoryManager::with_limit(created + 40 * 50 * 3 * 4 - 1);
+        let mut decoder = Decoder::with_memory_manager(&memory_manager)?;
+        assert_eq!(
+            decoder.decode(&sample).unwrap_err(),
+            DecodeError::MemoryLimitExceeded
+        );
+        // Also without subscribing to the basic info
+        let result = decoder
+            .events(&sample, JxlDecoderEvents::FULL_IMAGE)
+            .and_then(|mut events| events.next_event());
+        assert_eq!(result, Err(DecodeError::MemoryLimitExceeded));
+
+        // Enough for the planes, but not for the 8-bit output buffer as well
+        let memory_manager = MemoryManager::with_limit(created + 40 * 50 * 3 * 4);
+        let mut decoder = Decoder::with_memory_manager(&memory_manager)?;
+        assert_eq!(
+            decoder.decode(&sample).unwrap_err(),
+            DecodeError::MemoryLimitExceeded
+        );
+
+        let memory_manager = MemoryManager::with_limit(created + 1024 * 1024);
+        let mut decoder = Decoder::with_memory_manager(&memory_manager)?;
+        assert!(decoder.decode(&sample).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_planes_size() {
+        // SAFETY: Every field is an integer, a float, or a struct of them
+        let mut info: JxlBasicInfo = unsafe { MaybeUninit::zeroed().assume_init() };
+        (info.xsize, info.ysize) = (40, 50);
+        (info.num_color_channels, info.num_extra_channels) = (3, 1);
+        assert_eq!(planes_size(&info), 40 * 50 * 4 * 4);
+
+        info.have_animation = true.into();
+        assert_eq!(planes_size(&info), 40 * 50 * 4 * 4 * 2);
+
+        // A decompression bomb
+        (info.xsize, info.ysize) = (1 << 30, 1 << 30);
+        assert!(planes_size(&info) > 1 << 62);
+    }
+
+    #[test]
+    fn test_events() -> Result<(), DecodeError> {
+        let sample = std::fs::read("test/sample.jxl").unwrap();
+        let mut decoder = Decoder::new()?;
+        let format = JxlPixelFormat {
+            num_channels: 3,
+            data_type: JxlDataType::Uint8,
+            endianness: JxlEndianness::Native,
+            align: 0,
+        };
+
+        let mut events = decoder.events(
+            &sample,
+            jxl_dec_events!(
+                JxlDecoderStatus::BasicInfo,
+                JxlDecoderStatus::Frame,
+                JxlDecoderStatus::FullImage
+            ),
+        )?;
+        let mut seen = Vec::new();
+
+        loop {
+            let event = events.next_event()?;
+            seen.push(event);
+
+            match event {
+                Event::BasicInfo => assert_eq!(events.basic_info()?.xsize, 40),
+                Event::Frame => assert_eq!(events.frame_header()?.is_last, crate::JxlBool::True),
+                Event::NeedImageOutBuffer => {
+                    events.set_image_out_buffer(&format)?;
+                    assert!(events.take_image().is_none());
+                }
+                Event::Finished => break,
+                _ => (),
+            }
+        }
+
+        assert_eq!(
+            seen,
+            [
+                Event::BasicInfo,
+                Event::Frame,
+                Event::NeedImageOutBuffer,
+                Event::FullImage,
+                Event::Finished
+            ]
+        );
+        assert_eq!(events.take_image().map(|i| i.len()), Some(40 * 50 * 3));
+
+        Ok(())
+    }
+}
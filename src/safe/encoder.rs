@@ -0,0 +1,458 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::{
+    ffi::c_void,
+    mem,
+    ptr::{self, NonNull},
+};
+
+use super::{check_enc_status, EncodeError};
+use crate::{
+    common::{
+        JxlBasicInfo, JxlColorEncoding, JxlDataType, JxlEndianness, JxlParallelRunner,
+        JxlPixelFormat,
+    },
+    encoder::{
+        JxlEncoder, JxlEncoderAddImageFrame, JxlEncoderCloseInput, JxlEncoderCreate,
+        JxlEncoderDestroy, JxlEncoderFrameSettingId, JxlEncoderFrameSettingsCreate,
+        JxlEncoderFrameSettingsSetOption, JxlEncoderProcessOutput, JxlEncoderReset,
+        JxlEncoderSetBasicInfo, JxlEncoderSetColorEncoding, JxlEncoderSetFrameDistance,
+        JxlEncoderSetFrameLossless, JxlEncoderSetParallelRunner, JxlEncoderStatus,
+        JxlEncoderUseContainer,
+    },
+    memory_manager::MemoryManager,
+};
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+    impl Sealed for f32 {}
+}
+
+/// Sample types accepted by [`Encoder::encode`]
+///
+/// This trait is sealed, as libjxl reads the samples as the given [`JxlDataType`].
+pub trait PixelType: private::Sealed + Copy {
+    /// Data type of the sample
+    const DATA_TYPE: JxlDataType;
+    /// Bits per sample stored in the image
+    const BITS_PER_SAMPLE: u32;
+    /// Exponent bits per sample, 0 for integers
+    const EXPONENT_BITS_PER_SAMPLE: u32;
+}
+
+impl PixelType for u8 {
+    const DATA_TYPE: JxlDataType = JxlDataType::Uint8;
+    const BITS_PER_SAMPLE: u32 = 8;
+    const EXPONENT_BITS_PER_SAMPLE: u32 = 0;
+}
+
+impl PixelType for u16 {
+    const DATA_TYPE: JxlDataType = JxlDataType::Uint16;
+    const BITS_PER_SAMPLE: u32 = 16;
+    const EXPONENT_BITS_PER_SAMPLE: u32 = 0;
+}
+
+impl PixelType for f32 {
+    const DATA_TYPE: JxlDataType = JxlDataType::Float;
+    const BITS_PER_SAMPLE: u32 = 32;
+    const EXPONENT_BITS_PER_SAMPLE: u32 = 8;
+}
+
+/// Settings of an [`Encoder`], created by [`Encoder::builder`]
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct EncoderBuilder {
+    distance: f32,
+    effort: Option<i64>,
+    lossless: bool,
+    decoding_speed: Option<i64>,
+    use_container: bool,
+    memory_manager: Option<MemoryManager>,
+}
+
+impl Default for EncoderBuilder {
+    fn default() -> Self {
+        Self {
+            distance: 1.0,
+            effort: None,
+            lossless: false,
+            decoding_speed: None,
+            use_container: false,
+            memory_manager: None,
+        }
+    }
+}
+
+impl EncoderBuilder {
+    /// Butteraugli distance of lossy encoding, from 0.0 (mathematically lossless) to 15.0.
+    /// Default: 1.0 (visually lossless)
+    pub fn distance(mut self, distance: f32) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    /// Encoder effort, from 1 (fastest) to 9 (slowest). Default: 7
+    pub fn effort(mut self, effort: i64) -> Self {
+        self.effort = Some(effort);
+        self
+    }
+
+    /// Encode losslessly, ignoring the distance. Default: `false`
+    pub fn lossless(mut self, lossless: bool) -> Self {
+        self.lossless = lossless;
+        self
+    }
+
+    /// Decoding speed tier, from 0 (slowest decoding, best quality) to 4. Default: 0
+    pub fn decoding_speed(mut self, tier: i64) -> Self {
+        self.decoding_speed = Some(tier);
+        self
+    }
+
+    /// Wrap the codestream in an ISOBMFF container. Default: `false`
+    pub fn use_container(mut self, use_container: bool) -> Self {
+        self.use_container = use_container;
+        self
+    }
+
+    /// Allocate through `memory_manager` instead of the default one
+    pub fn memory_manager(mut self, memory_manager: &MemoryManager) -> Self {
+        self.memory_manager = Some(memory_manager.clone());
+        self
+    }
+
+    /// Create the encoder
+    ///
+    /// # Errors
+    /// Returns [`EncodeError::CannotCreateEncoder`] if libjxl fails to create one, including when
+    /// the memory limit is too low
+    pub fn build(self) -> Result<Encoder, EncodeError> {
+        let memory_manager = self
+            .memory_manager
+            .as_ref()
+            .map(MemoryManager::as_jxl_memory_manager);
+        let memory_manager = memory_manager.as_ref().map_or(ptr::null(), ptr::from_ref);
+
+        NonNull::new(unsafe { JxlEncoderCreate(memory_manager) })
+            .map(|ptr| Encoder {
+                ptr,
+                settings: self,
+                runner: None,
+            })
+            .ok_or(EncodeError::CannotCreateEncoder)
+    }
+}
+
+/// Owned `JxlEncoder`, destroyed on drop
+pub struct Encoder {
+    ptr: NonNull<JxlEncoder>,
+    settings: EncoderBuilder,
+    runner: Option<(JxlParallelRunner, *mut c_void)>,
+}
+
+// The encoder is not shared with anything else, it can be moved between threads
+unsafe impl Send for Encoder {}
+
+impl Encoder {
+    /// Settings to create an encoder
+    pub fn builder() -> EncoderBuilder {
+        EncoderBuilder::default()
+    }
+
+    /// Create an encoder with the default settings
+    ///
+    /// # Errors
+    /// Returns [`EncodeError::CannotCreateEncoder`] if libjxl fails to create one
+    pub fn new() -> Result<Self, EncodeError> {
+        Self::builder().build()
+    }
+
+    /// Raw pointer to use with the functions in [`crate::encoder`]
+    ///
+    /// The encoder is reset at the start of every [`Encoder::encode`].
+    #[must_use]
+    pub fn as_ptr(&self) -> *mut JxlEncoder {
+        self.ptr.as_ptr()
+    }
+
+    /// Set the parallel runner for multithreading, used by every following encoding
+    ///
+    /// # Safety
+    /// `runner_opaque` must be valid for `runner` until the encoder is dropped
+    pub unsafe fn set_parallel_runner(
+        &mut self,
+        runner: JxlParallelRunner,
+        runner_opaque: *mut c_void,
+    ) {
+        self.runner = Some((runner, runner_opaque));
+    }
+
+    /// Encode interleaved `data` of `xsize` by `ysize` pixels with `num_channels` channels
+    ///
+    /// 1 and 2 channels are grayscale, 3 and 4 are RGB, with alpha as the last channel if there
+    /// is one. Integer samples are in sRGB and float samples are in linear sRGB.
+    ///
+    /// # Errors
+    /// Returns [`EncodeError::InvalidInput`] if the size of `data` doesn't match,
+    /// [`EncodeError::CannotCreateFrameSettings`] if libjxl fails to allocate them, including when
+    /// the memory limit is too low, or the error from libjxl if the encoding fails
+    pub fn encode<T: PixelType>(
+        &mut self,
+        data: &[T],
+        xsize: u32,
+        ysize: u32,
+        num_channels: u32,
+    ) -> Result<Vec<u8>, EncodeError> {
+        let expected = u64::from(xsize) * u64::from(ysize) * u64::from(num_channels);
+        if !(1..=4).contains(&num_channels) || u64::try_from(data.len()) != Ok(expected) {
+            return Err(EncodeError::InvalidInput);
+        }
+
+        unsafe {
+            self.setup(
+                xsize,
+                ysize,
+                num_channels,
+                T::BITS_PER_SAMPLE,
+                T::EXPONENT_BITS_PER_SAMPLE,
+            )?;
+
+            let frame_settings = JxlEncoderFrameSettingsCreate(self.as_ptr(), ptr::null());
+            if frame_settings.is_null() {
+                return Err(EncodeError::CannotCreateFrameSettings);
+            }
+            let settings = &self.settings;
+            if settings.lossless {
+                check_enc_status(JxlEncoderSetFrameLossless(frame_settings, true))?;
+            } else {
+                check_enc_status(JxlEncoderSetFrameDistance(
+                    frame_settings,
+                    settings.distance,
+                ))?;
+            }
+            for (id, value) in [
+                (JxlEncoderFrameSettingId::Effort, settings.effort),
+                (
+                    JxlEncoderFrameSettingId::DecodingSpeed,
+                    settings.decoding_speed,
+                ),
+            ] {
+                if let Some(value) = value {
+                    check_enc_status(JxlEncoderFrameSettingsSetOption(frame_settings, id, value))?;
+                }
+            }
+
+            let format = JxlPixelFormat {
+                num_channels,
+                data_type: T::DATA_TYPE,
+                endianness: JxlEndianness::Native,
+                align: 0,
+            };
+            check_enc_status(JxlEncoderAddImageFrame(
+                frame_settings,
+                ptr::addr_of!(format),
+                data.as_ptr().cast(),
+                mem::size_of_val(data),
+            ))?;
+            JxlEncoderCloseInput(self.as_ptr());
+
+            self.process_output()
+        }
+    }
+
+    unsafe fn setup(
+        &mut self,
+        xsize: u32,
+        ysize: u32,
+        num_channels: u32,
+        bits_per_sample: u32,
+        exponent_bits_per_sample: u32,
+    ) -> Result<(), EncodeError> {
+        let enc = self.as_ptr();
+        JxlEncoderReset(enc);
+        if let Some((runner, runner_opaque)) = self.runner {
+            check_enc_status(JxlEncoderSetParallelRunner(enc, runner, runner_opaque))?;
+        }
+        check_enc_status(JxlEncoderUseContainer(enc, self.settings.use_container))?;
+
+        let mut basic_info = JxlBasicInfo::default_for_encoder();
+        let is_gray = num_channels < 3;
+        let has_alpha = matches!(num_channels, 2 | 4);
+
+        basic_info.xsize = xsize;
+        basic_info.ysize = ysize;
+        basic_info.bits_per_sample = bits_per_sample;
+        basic_info.exponent_bits_per_sample = exponent_bits_per_sample;
+        basic_info.num_color_channels = if is_gray { 1 } else { 3 };
+        if has_alpha {
+            basic_info.num_extra_channels = 1;
+            basic_info.alpha_bits = bits_per_sample;
+            basic_info.alpha_exponent_bits = exponent_bits_per_sample;
+        }
+        basic_info.uses_original_profile = self.settings.lossless.into();
+        check_enc_status(JxlEncoderSetBasicInfo(enc, ptr::addr_of!(basic_info)))?;
+
+        let color_encoding = if exponent_bits_per_sample > 0 {
+            JxlColorEncoding::linear_srgb(is_gray)
+        } else {
+            JxlColorEncoding::srgb(is_gray)
+        };
+        check_enc_status(JxlEncoderSetColorEncoding(
+            enc,
+            ptr::addr_of!(color_encoding),
+        ))
+    }
+
+    unsafe fn process_output(&mut self) -> Result<Vec<u8>, EncodeError> {
+        const CHUNK_SIZE: usize = 1024 * 512; // 512 KB is a good initial value
+
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        let mut next_out = buffer.as_mut_ptr();
+        let mut avail_out = buffer.len();
+
+        loop {
+            let status = JxlEncoderProcessOutput(
+                self.as_ptr(),
+                ptr::addr_of_mut!(next_out),
+                ptr::addr_of_mut!(avail_out),
+            );
+            if status != JxlEncoderStatus::NeedMoreOutput {
+                check_enc_status(status)?;
+                break;
+            }
+
+            let offset = buffer.len() - avail_out;
+            buffer.resize(buffer.len() * 2, 0);
+            next_out = buffer.as_mut_ptr().add(offset);
+            avail_out = buffer.len() - offset;
+        }
+
+        buffer.truncate(buffer.len() - avail_out);
+        Ok(buffer)
+    }
+}
+
+impl Drop for Encoder {
+    fn drop(&mut self) {
+        unsafe { JxlEncoderDestroy(self.as_ptr()) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use image::io::Reader as ImageReader;
+
+    use super::*;
+    use crate::{common::JxlBool, safe::Decoder};
+
+    #[test]
+    fn test_encode() -> Result<(), Box<dyn std::error::Error>> {
+        let img = ImageReader::open("test/sample.png")?.decode()?.into_rgb8();
+        let mut encoder = Encoder::builder().distance(2.0).effort(3).build()?;
+
+        let output = encoder.encode(img.as_raw(), img.width(), img.height(), 3)?;
+        let image = Decoder::new()?.decode(&output)?;
+        assert_eq!((image.info.xsize, image.info.ysize), img.dimensions());
+
+        // Reuse the encoder with different input
+        let gray = vec![1000u16; 40 * 50];
+        let output = encoder.encode(&gray, 40, 50, 1)?;
+        let image = Decoder::new()?.decode(&output)?;
+        assert_eq!(image.info.num_color_channels, 1);
+        assert_eq!(image.info.bits_per_sample, 16);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_lossless() -> Result<(), Box<dyn std::error::Error>> {
+        let img = ImageReader::open("test/sample.png")?.decode()?.into_rgba8();
+        let mut encoder = Encoder::builder()
+            .lossless(true)
+            .decoding_speed(1)
+            .use_container(true)
+            .build()?;
+
+        let output = encoder.encode(img.as_raw(), img.width(), img.height(), 4)?;
+        let signature = unsafe { crate::JxlSignatureCheck(output.as_ptr(), output.len()) };
+        assert_eq!(signature, crate::JxlSignature::Container);
+
+        let image = Decoder::new()?.decode(&output)?;
+        assert_eq!(image.info.alpha_bits, 8);
+        assert_eq!(image.info.uses_original_profile, JxlBool::True);
+        assert_eq!(&image.data, img.as_raw());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_memory_limit() -> Result<(), EncodeError> {
+        let pixels = vec![0u8; 40 * 50 * 3];
+
+        let memory_manager = MemoryManager::new();
+        let mut encoder = Encoder::builder().memory_manager(&memory_manager).build()?;
+        encoder.encode(&pixels, 40, 50, 3)?;
+        drop(encoder);
+        assert_eq!(memory_manager.current(), 0);
+        assert!(memory_manager.peak() > 0);
+
+        let memory_manager = MemoryManager::with_limit(memory_manager.peak() / 2);
+        let result = Encoder::builder()
+            .memory_manager(&memory_manager)
+            .build()
+            .and_then(|mut encoder| encoder.encode(&pixels, 40, 50, 3));
+        assert!(result.is_err());
+
+        // Enough to create the encoder, but not its frame settings
+        let memory_manager = MemoryManager::new();
+        let encoder = Encoder::builder().memory_manager(&memory_manager).build()?;
+        let created = memory_manager.current();
+        drop(encoder);
+        let memory_manager = MemoryManager::with_limit(created);
+        let mut encoder = Encoder::builder().memory_manager(&memory_manager).build()?;
+        assert_eq!(
+            encoder.encode(&pixels, 40, 50, 3),
+            Err(EncodeError::CannotCreateFrameSettings)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_error() -> Result<(), EncodeError> {
+        let mut encoder = Encoder::builder().effort(42).build()?;
+
+        assert_eq!(
+            encoder.encode(&[0u8; 10], 40, 50, 3),
+            Err(EncodeError::InvalidInput)
+        );
+        assert_eq!(
+            encoder.encode(&[0u8; 10], 2, 1, 5),
+            Err(EncodeError::InvalidInput)
+        );
+        assert_eq!(
+            encoder.encode(&[0u8; 6], 2, 1, 3),
+            Err(EncodeError::GenericError)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::{error::Error, fmt};
+
+use crate::{decoder::JxlDecoderStatus, encoder::JxlEncoderStatus};
+
+/// Errors from decoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Unable to create the decoder
+    CannotCreateDecoder,
+    /// The decoder returned `JxlDecoderStatus::Error`
+    GenericError,
+    /// The input ended before the decoding finished
+    NeedMoreInput,
+    /// The decoder returned a status that is not expected at this point
+    UnexpectedStatus(JxlDecoderStatus),
+    /// The image output callback panicked
+    CallbackPanicked,
+    /// Decoding the image would use more memory than the limit of the memory manager
+    MemoryLimitExceeded,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CannotCreateDecoder => write!(f, "Cannot create a decoder"),
+            Self::GenericError => write!(f, "Generic error in the decoder"),
+            Self::NeedMoreInput => write!(f, "Input ended before the decoding finished"),
+            Self::UnexpectedStatus(status) => write!(f, "Unexpected decoder status: {status:?}"),
+            Self::CallbackPanicked => write!(f, "Image output callback panicked"),
+            Self::MemoryLimitExceeded => write!(f, "Image too large for the memory limit"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+/// Convert a status returned by the decoder into a [`Result`]
+///
+/// # Errors
+/// Returns the corresponding [`DecodeError`] if `status` is not `JxlDecoderStatus::Success`
+pub fn check_dec_status(status: JxlDecoderStatus) -> Result<(), DecodeError> {
+    match status {
+        JxlDecoderStatus::Success => Ok(()),
+        JxlDecoderStatus::Error => Err(DecodeError::GenericError),
+        JxlDecoderStatus::NeedMoreInput => Err(DecodeError::NeedMoreInput),
+        _ => Err(DecodeError::UnexpectedStatus(status)),
+    }
+}
+
+/// Errors from encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeError {
+    /// Unable to create the encoder
+    CannotCreateEncoder,
+    /// Unable to create the settings of a frame
+    CannotCreateFrameSettings,
+    /// The encoder returned `JxlEncoderStatus::Error`
+    GenericError,
+    /// The encoder returned `JxlEncoderStatus::NotSupported`
+    NotSupported,
+    /// The size of the pixel data doesn't match the dimensions and channels
+    InvalidInput,
+    /// The encoder returned a status that is not expected at this point
+    UnexpectedStatus(JxlEncoderStatus),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CannotCreateEncoder => write!(f, "Cannot create an encoder"),
+            Self::CannotCreateFrameSettings => write!(f, "Cannot create the frame settings"),
+            Self::GenericError => write!(f, "Generic error in the encoder"),
+            Self::NotSupported => write!(f, "Operation not supported by the encoder"),
+            Self::InvalidInput => write!(f, "Pixel data doesn't match the image size"),
+            Self::UnexpectedStatus(status) => write!(f, "Unexpected encoder status: {status:?}"),
+        }
+    }
+}
+
+impl Error for EncodeError {}
+
+/// Convert a status returned by the encoder into a [`Result`]
+///
+/// # Errors
+/// Returns the corresponding [`EncodeError`] if `status` is not `JxlEncoderStatus::Success`
+pub fn check_enc_status(status: JxlEncoderStatus) -> Result<(), EncodeError> {
+    match status {
+        JxlEncoderStatus::Success => Ok(()),
+        JxlEncoderStatus::Error => Err(EncodeError::GenericError),
+        JxlEncoderStatus::NotSupported => Err(EncodeError::NotSupported),
+        _ => Err(EncodeError::UnexpectedStatus(status)),
+    }
+}
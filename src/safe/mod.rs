@@ -0,0 +1,30 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Safe wrappers over the raw bindings.
+//!
+//! Handles returned by libjxl are owned by Rust types that destroy them on drop, and statuses are
+//! converted into [`Result`]s. The raw pointers are still available through `as_ptr` for anything
+//! not covered here.
+
+mod error;
+
+pub mod callback;
+pub mod decoder;
+pub mod encoder;
+
+pub use {callback::*, decoder::*, encoder::*, error::*};